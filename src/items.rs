@@ -1,19 +1,45 @@
+pub mod battery;
+pub mod breaker;
+pub mod definition;
 pub mod generator;
 pub mod light;
 pub mod power_pole;
+pub mod registry;
+pub mod relay;
+pub mod solar_panel;
+pub mod switch;
+pub mod wind_turbine;
 
+use crate::items::battery::BatteryPlugin;
+use crate::items::breaker::BreakerPlugin;
 use crate::items::generator::GeneratorPlugin;
 use crate::items::light::LightPlugin;
 use crate::items::power_pole::PowerPolePlugin;
+use crate::items::registry::ItemRegistry;
+use crate::items::relay::RelayPlugin;
+use crate::items::solar_panel::SolarPanelPlugin;
+use crate::items::switch::SwitchPlugin;
+use crate::items::wind_turbine::WindTurbinePlugin;
 use bevy::prelude::*;
 
 pub struct ItemsPlugin;
 
 impl Plugin for ItemsPlugin {
     fn build(&self, app: &mut App) {
-        app
+        // Loaded up front so the build tool and UI can read it during Startup
+        app.insert_resource(ItemRegistry::load())
             // .add_systems(Startup, setup)
-            .add_plugins((GeneratorPlugin, PowerPolePlugin, LightPlugin));
+            .add_plugins((
+                GeneratorPlugin,
+                PowerPolePlugin,
+                LightPlugin,
+                BatteryPlugin,
+                SolarPanelPlugin,
+                WindTurbinePlugin,
+                SwitchPlugin,
+                RelayPlugin,
+                BreakerPlugin,
+            ));
     }
 }
 
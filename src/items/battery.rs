@@ -0,0 +1,36 @@
+use crate::power::power_storage::PowerStorage;
+use crate::world::grid::Material2dHandle;
+use bevy::color::Mix;
+use bevy::color::palettes::basic::{GREEN, RED};
+use bevy::prelude::*;
+
+pub struct BatteryPlugin;
+
+#[derive(Component)]
+pub struct Battery;
+
+impl Plugin for BatteryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, update_battery_visuals);
+    }
+}
+
+/// Batteries whose charge moved since last frame
+type ChangedBatteries<'w, 's> = Query<
+    'w,
+    's,
+    (&'static PowerStorage, &'static Material2dHandle),
+    (With<Battery>, Changed<PowerStorage>),
+>;
+
+fn update_battery_visuals(
+    batteries: ChangedBatteries,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (storage, material_handle) in batteries.iter() {
+        if let Some(material) = materials.get_mut(&material_handle.0) {
+            // Empty batteries are red, full ones green
+            material.color = Color::from(RED).mix(&Color::from(GREEN), storage.level());
+        }
+    }
+}
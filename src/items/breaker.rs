@@ -0,0 +1,91 @@
+use crate::items::switch::TOGGLE_BUTTON;
+use crate::power::overload::{NetworkTripped, Tripped, reset_network};
+use crate::power::power_gate::PowerGate;
+use crate::power::power_network::PowerNetworks;
+use crate::world::grid::GridCursor;
+use crate::world::tile_index::TileIndex;
+use bevy::prelude::*;
+
+pub struct BreakerPlugin;
+
+impl Plugin for BreakerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (trip_breakers, breaker_reset_input));
+    }
+}
+
+/// Opens when its network trips and stays open until clicked
+#[derive(Component)]
+pub struct Breaker;
+
+fn trip_breakers(
+    mut events: EventReader<NetworkTripped>,
+    networks: Res<PowerNetworks>,
+    mut breakers: Query<&mut PowerGate, With<Breaker>>,
+) {
+    for event in events.read() {
+        let Some(network) = networks.get(event.network) else {
+            continue;
+        };
+        for member in network.members.iter() {
+            if let Ok(mut gate) = breakers.get_mut(*member) {
+                gate.closed = false;
+            }
+        }
+    }
+}
+
+/// Clicking a tripped breaker closes it and brings its network back. A
+/// network without breakers is reset by clicking any of its tripped items.
+fn breaker_reset_input(
+    buttons: Res<ButtonInput<MouseButton>>,
+    cursor: GridCursor,
+    tile_index: Res<TileIndex>,
+    networks: Res<PowerNetworks>,
+    breakers: Query<&PowerGate, With<Breaker>>,
+    tripped: Query<(), With<Tripped>>,
+    mut commands: Commands,
+) {
+    if !buttons.just_pressed(TOGGLE_BUTTON) {
+        return;
+    }
+    let Some((item, network)) = cursor
+        .grid_position()
+        .and_then(|pos| tile_index.item(pos))
+        .and_then(|item| Some((item, networks.network_of(item)?)))
+    else {
+        return;
+    };
+
+    let resettable = match breakers.get(item) {
+        Ok(gate) => !gate.closed,
+        Err(_) => {
+            tripped.contains(item)
+                && networks.get(network).is_some_and(|network| {
+                    !network
+                        .members
+                        .iter()
+                        .any(|member| breakers.contains(*member))
+                })
+        }
+    };
+    if !resettable {
+        return;
+    }
+
+    println!("Resetting tripped network");
+    commands.queue(move |world: &mut World| {
+        reset_network(world, network);
+        let mut breakers = world.query_filtered::<Entity, With<Breaker>>();
+        let networks = world.resource::<PowerNetworks>();
+        let in_network: Vec<Entity> = breakers
+            .iter(world)
+            .filter(|breaker| networks.network_of(*breaker) == Some(network))
+            .collect();
+        for breaker in in_network {
+            if let Some(mut gate) = world.get_mut::<PowerGate>(breaker) {
+                gate.closed = true;
+            }
+        }
+    });
+}
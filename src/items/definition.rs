@@ -0,0 +1,145 @@
+use crate::items::generator::FuelType;
+use crate::items::relay::RelayCondition;
+use crate::wire_system::port::PortDefinition;
+use crate::world::grid::Footprint;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Registry key of an item, taken from the file stem of its definition
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ItemId(pub String);
+
+impl ItemId {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+}
+
+impl From<&str> for ItemId {
+    fn from(id: &str) -> Self {
+        Self::new(id)
+    }
+}
+
+impl fmt::Display for ItemId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// An item as described by a `.ron` file under `assets/items/`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ItemDefinition {
+    #[serde(skip)]
+    pub id: ItemId,
+    pub name: String,
+    /// Digit key that selects this item as the build tool
+    #[serde(default)]
+    pub hotkey: Option<u8>,
+    pub shape: ItemShape,
+    /// Tiles covered when facing north, 1x1 unless given
+    #[serde(default)]
+    pub footprint: Footprint,
+    /// Sides that take wires; without any the item takes wires anywhere
+    #[serde(default)]
+    pub ports: Vec<PortDefinition>,
+    /// Tiles between items placed by dragging, never less than the footprint
+    #[serde(default = "default_drag_spacing")]
+    pub drag_spacing: i32,
+    /// Hex color, e.g. "#A52A2A"
+    pub color: String,
+    pub max_connections: u8,
+    /// Longest wire, in tiles, this item accepts
+    #[serde(default = "default_reach")]
+    pub reach: f32,
+    #[serde(default)]
+    pub pole: bool,
+    #[serde(default)]
+    pub source: Option<SourceDefinition>,
+    #[serde(default)]
+    pub consumer: Option<ConsumerDefinition>,
+    #[serde(default)]
+    pub storage: Option<StorageDefinition>,
+    #[serde(default)]
+    pub gate: Option<GateDefinition>,
+}
+
+fn default_reach() -> f32 {
+    6.0
+}
+
+fn default_drag_spacing() -> i32 {
+    1
+}
+
+fn default_glow_radius() -> f32 {
+    40.0
+}
+
+fn default_spin_up() -> f32 {
+    10.0
+}
+
+fn default_spin_down() -> f32 {
+    20.0
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum ItemShape {
+    Circle { radius: f32 },
+    Rectangle { width: f32, height: f32 },
+    Triangle { size: f32 },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum SourceDefinition {
+    /// Starts with `fuel` units of `fuel_type` in a tank holding `tank` units.
+    /// Output follows the load up to `max_output` watts, changing by at most
+    /// `spin_up` / `spin_down` watts per second.
+    Generator {
+        fuel: f32,
+        #[serde(default)]
+        fuel_type: FuelType,
+        tank: f32,
+        max_output: f32,
+        #[serde(default = "default_spin_up")]
+        spin_up: f32,
+        #[serde(default = "default_spin_down")]
+        spin_down: f32,
+    },
+    /// Gives up to `peak_output` watts at noon and nothing at night
+    Solar { peak_output: f32 },
+    /// Gives up to `rated_output` watts depending on the wind
+    Wind { rated_output: f32 },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum ConsumerDefinition {
+    /// Draws `demand` watts while lit and dims when the network can't supply
+    /// it all; glows `glow_radius` pixels around itself
+    Light {
+        demand: f32,
+        #[serde(default = "default_glow_radius")]
+        glow_radius: f32,
+    },
+}
+
+/// Items that can break the circuit through them
+#[derive(Debug, Clone, Deserialize)]
+pub enum GateDefinition {
+    /// Flipped by hand
+    Switch,
+    /// Opens and closes on its own following a condition
+    Relay(RelayCondition),
+    /// Opens when its network trips from overload, closed again by hand
+    Breaker,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StorageDefinition {
+    /// Watt-seconds
+    pub capacity: f32,
+    pub max_charge_rate: f32,
+    pub max_discharge_rate: f32,
+}
@@ -1,19 +1,200 @@
 use crate::power::power_source::PowerSource;
-use crate::wire_system::ConnectionPoint;
-use crate::world::grid;
-use crate::world::grid::{GridPosition, Material2dHandle};
-use bevy::color::palettes::basic::{GREEN, RED};
+use crate::world::grid::Material2dHandle;
+use bevy::color::palettes::basic::{GREEN, RED, YELLOW};
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
 
 pub struct GeneratorPlugin;
 
+/// What a generator burns. Denser fuels release more energy per unit and
+/// each unit burns for longer.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FuelType {
+    #[default]
+    Wood,
+    Coal,
+    Diesel,
+}
+
+impl FuelType {
+    pub fn name(self) -> &'static str {
+        match self {
+            FuelType::Wood => "Wood",
+            FuelType::Coal => "Coal",
+            FuelType::Diesel => "Diesel",
+        }
+    }
+
+    /// Watt-seconds released by one unit
+    pub fn energy(self) -> f32 {
+        match self {
+            FuelType::Wood => 20.0,
+            FuelType::Coal => 75.0,
+            FuelType::Diesel => 200.0,
+        }
+    }
+
+    /// Seconds one unit lasts
+    pub fn burn_time(self) -> f32 {
+        match self {
+            FuelType::Wood => 1.0,
+            FuelType::Coal => 2.5,
+            FuelType::Diesel => 5.0,
+        }
+    }
+
+    /// Wattage at which one unit lasts exactly its burn time
+    pub fn power(self) -> f32 {
+        self.energy() / self.burn_time()
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            FuelType::Wood => FuelType::Coal,
+            FuelType::Coal => FuelType::Diesel,
+            FuelType::Diesel => FuelType::Wood,
+        }
+    }
+}
+
+/// Why fuel couldn't be added to a generator
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RefuelError {
+    TankFull,
+    /// The tank still holds some of another fuel
+    MixedFuel(FuelType),
+}
+
+impl fmt::Display for RefuelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RefuelError::TankFull => write!(f, "tank is full"),
+            RefuelError::MixedFuel(fuel) => {
+                write!(f, "tank still holds {}", fuel.name())
+            }
+        }
+    }
+}
+
+/// Fraction of `max_output` kept spinning above the load, so new demand
+/// shows up as a fully drawn generator and it spins up to meet it
+const RESERVE_FRACTION: f32 = 0.05;
+/// Load within this many watts of the output counts as fully drawn
+const LOAD_EPSILON: f32 = 1e-3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeneratorMode {
+    /// Has fuel but nothing draws from it
+    Idle,
+    Running,
+    OutOfFuel,
+}
+
 #[derive(Component)]
 pub struct Generator {
+    pub(crate) fuel_type: FuelType,
+    /// Units of `fuel_type` in the tank
     pub(crate) fuel_amount: f32,
+    pub(crate) tank_capacity: f32,
+    /// Wattage currently offered, moving toward the load at the spin rates
     pub(crate) output: f32,
-    // pub(crate) max_output: f32,
+    pub(crate) max_output: f32,
+    /// Watts per second the output can rise
+    pub(crate) spin_up: f32,
+    /// Watts per second the output can fall
+    pub(crate) spin_down: f32,
+    /// Burning fuel, i.e. something draws from it
     pub(crate) is_active: bool,
+    /// Finishes once per unit of fuel burnt; ticks in proportion to the load
     pub(crate) burn_timer: Timer,
+    /// Mode shown by the generator's colour, `None` until its first tick
+    pub(crate) mode: Option<GeneratorMode>,
+}
+
+impl Generator {
+    pub fn new(fuel_type: FuelType, fuel_amount: f32, tank_capacity: f32, max_output: f32) -> Self {
+        Self {
+            is_active: false,
+            fuel_type,
+            fuel_amount: fuel_amount.min(tank_capacity),
+            tank_capacity,
+            output: max_output * RESERVE_FRACTION,
+            max_output,
+            spin_up: max_output,
+            spin_down: max_output,
+            burn_timer: Timer::from_seconds(fuel_type.burn_time(), TimerMode::Repeating),
+            mode: None,
+        }
+    }
+
+    /// Advances the generator by `dt` seconds while the network draws `load`
+    /// watts from it
+    pub fn step(&mut self, dt: f32, load: f32) -> GeneratorMode {
+        if self.fuel_amount <= 0.0 {
+            self.output = 0.0;
+            self.is_active = false;
+            return GeneratorMode::OutOfFuel;
+        }
+
+        // A fully drawn generator is short of what the network wants, so it
+        // spins up; otherwise it follows the load down, keeping a reserve
+        let load = load.clamp(0.0, self.output);
+        let reserve = self.max_output * RESERVE_FRACTION;
+        let target = if load >= self.output - LOAD_EPSILON {
+            self.max_output
+        } else {
+            (load + reserve).min(self.max_output)
+        };
+        self.output = if target > self.output {
+            (self.output + self.spin_up * dt).min(target)
+        } else {
+            (self.output - self.spin_down * dt).max(target)
+        };
+
+        // A unit lasts its burn time when drawn at the fuel's rated power, so
+        // fuel goes exactly as fast as energy is taken out
+        self.is_active = load > 0.0;
+        if self.is_active {
+            let burn_rate = load / self.fuel_type.power();
+            self.burn_timer
+                .tick(Duration::from_secs_f32(dt * burn_rate));
+            let burnt = self.burn_timer.times_finished_this_tick() as f32;
+            self.fuel_amount = (self.fuel_amount - burnt).max(0.0);
+        }
+
+        if self.is_active {
+            GeneratorMode::Running
+        } else {
+            GeneratorMode::Idle
+        }
+    }
+
+    /// Switches to `fuel_type`, restarting the burn cycle at its burn time
+    pub fn set_fuel_type(&mut self, fuel_type: FuelType) {
+        self.fuel_type = fuel_type;
+        self.burn_timer = Timer::from_seconds(fuel_type.burn_time(), TimerMode::Repeating);
+    }
+
+    /// Pours up to `amount` units of `fuel_type` into the tank, returning how
+    /// much fit. An empty tank takes any fuel; otherwise it has to match.
+    pub fn refuel(&mut self, fuel_type: FuelType, amount: f32) -> Result<f32, RefuelError> {
+        if self.fuel_amount <= 0.0 {
+            if fuel_type != self.fuel_type {
+                self.set_fuel_type(fuel_type);
+            }
+        } else if fuel_type != self.fuel_type {
+            return Err(RefuelError::MixedFuel(self.fuel_type));
+        }
+
+        let added = amount.min(self.tank_capacity - self.fuel_amount);
+        if added <= 0.0 {
+            return Err(RefuelError::TankFull);
+        }
+        self.fuel_amount += added;
+        Ok(added)
+    }
 }
 
 impl Plugin for GeneratorPlugin {
@@ -24,93 +205,44 @@ impl Plugin for GeneratorPlugin {
 
 pub fn tick_power(
     time: Res<Time>,
-    mut generator: Query<(&mut Generator, &Material2dHandle), With<Generator>>,
+    mut generators: Query<(&mut Generator, &PowerSource, &Material2dHandle)>,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    for (mut generator, material_handle) in generator.iter_mut() {
-        let has_fuel = generator.fuel_amount > 0.0;
-        // let can_output = generator.output < generator.max_output;
-        let out_of_fuel = generator.fuel_amount == 0.0;
-
-        // if (can_output) {
-        if has_fuel {
-            generator.burn_timer.tick(time.delta());
-
-            generator.is_active = true;
-
-            if generator.burn_timer.finished() {
-                generator.fuel_amount -= 1.0;
-                println!("Fuel left: {}", generator.fuel_amount);
-            }
-
-            generator.output += 1.0;
-
-            if let Some(material) = materials.get_mut(&material_handle.0) {
-                material.color = Color::from(GREEN);
-            }
+    for (mut generator, power_source, material_handle) in generators.iter_mut() {
+        let mode = generator.step(time.delta_secs(), power_source.load);
+        if generator.mode == Some(mode) {
+            continue;
         }
+        generator.mode = Some(mode);
 
-        if out_of_fuel {
-            generator.is_active = false;
-
-            if let Some(material) = materials.get_mut(&material_handle.0) {
-                material.color = Color::from(RED);
+        let color = match mode {
+            GeneratorMode::Running => GREEN,
+            GeneratorMode::Idle => YELLOW,
+            GeneratorMode::OutOfFuel => {
+                println!("No fuel left!");
+                RED
             }
-
-            println!("No fuel left!")
+        };
+        if let Some(material) = materials.get_mut(&material_handle.0) {
+            material.color = Color::from(color);
         }
-        // } else {
-        // generator.is_active = false;
-        //
-        // if let Some(material) = materials.get_mut(&material_handle.0) {
-        //     material.color = Color::from(YELLOW);
-        // }
-        //
-        // println!("Battery is full!");
-        // println!("Delta: {:?}", time.delta());
-        // }
     }
 }
 
 pub fn sync_power_source(mut generators: Query<(&Generator, &mut PowerSource), With<Generator>>) {
     for (generator, mut power_source) in generators.iter_mut() {
-        power_source.powered = generator.is_active;
+        // Idle generators still offer their reserve so new demand can draw on it
+        let powered = generator.mode != Some(GeneratorMode::OutOfFuel);
+        let output = if powered { generator.output } else { 0.0 };
+        let capacity = if powered { generator.max_output } else { 0.0 };
+        // Only write on change so a steady generator doesn't re-dirty its network
+        if power_source.powered != powered
+            || power_source.output != output
+            || power_source.capacity != capacity
+        {
+            power_source.powered = powered;
+            power_source.output = output;
+            power_source.capacity = capacity;
+        }
     }
 }
-
-pub fn spawn_generator(
-    commands: &mut Commands,
-    pos: GridPosition,
-    mut meshes: ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<ColorMaterial>>,
-) -> Entity {
-    let gen_material_handle = materials.add(ColorMaterial::from_color(RED));
-    let triangle_size = 5.0;
-
-    commands
-        .spawn((
-            Name::new("Generator"),
-            Generator {
-                is_active: false,
-                fuel_amount: 5.0,
-                output: 0.0,
-                // max_output: 20.0,
-                burn_timer: Timer::from_seconds(1.0, TimerMode::Repeating),
-            },
-            Mesh2d(meshes.add(Triangle2d::new(
-                Vec2::Y * triangle_size,
-                Vec2::new(-triangle_size, -triangle_size),
-                Vec2::new(triangle_size, -triangle_size),
-            ))),
-            Material2dHandle(gen_material_handle.clone()),
-            MeshMaterial2d(gen_material_handle.clone()),
-            // Render above tile
-            Transform::from_translation(grid::grid_to_world(pos) + Vec3::Z),
-            pos,
-            // Single connection point
-            ConnectionPoint::new(1),
-            // Generators are power sources
-            PowerSource::default(),
-        ))
-        .id()
-}
@@ -1,13 +1,45 @@
 use crate::power::power_consumer::PowerConsumer;
-use crate::wire_system::ConnectionPoint;
-use crate::world::grid;
-use crate::world::grid::{GridPosition, Material2dHandle};
+use crate::world::clock::WorldClock;
+use crate::world::grid::{GridCursor, Material2dHandle};
+use crate::world::tile_index::TileIndex;
+use bevy::color::Mix;
 use bevy::color::palettes::basic::GRAY;
-use bevy::color::palettes::css::GREY;
 use bevy::prelude::*;
 
+/// Flips the manual switch of the light under the cursor
+const LIGHT_SWITCH_KEY: KeyCode = KeyCode::KeyL;
+const GLOW_COLOR: Color = Color::srgb(1.0, 0.9, 0.6);
+const GLOW_ALPHA: f32 = 0.25;
+/// Relative to the light, which puts the glow above the ambient overlay
+const GLOW_Z: f32 = 10.0;
+
 #[derive(Component)]
-pub struct Light;
+pub struct Light {
+    /// Wattage drawn while lit
+    pub demand: f32,
+    /// Radius of the glow around a lit light, in pixels
+    pub glow_radius: f32,
+    /// Keeps the light on in daylight too
+    pub switched_on: bool,
+}
+
+impl Light {
+    pub fn new(demand: f32, glow_radius: f32) -> Self {
+        Self {
+            demand,
+            glow_radius,
+            switched_on: false,
+        }
+    }
+
+    /// Lights come on by themselves at night
+    pub fn is_lit(&self, clock: &WorldClock) -> bool {
+        self.switched_on || clock.is_dark()
+    }
+}
+
+#[derive(Component)]
+struct LightGlow;
 
 pub struct LightPlugin;
 
@@ -15,48 +47,120 @@ impl Plugin for LightPlugin {
     fn build(&self, app: &mut App) {
         app
             // .add_systems(Startup, setup)
-            .add_systems(Update, update_light_visuals);
+            .add_observer(spawn_light_glow)
+            .add_systems(
+                Update,
+                (
+                    light_switch_input,
+                    update_light_demand,
+                    update_light_visuals,
+                )
+                    .chain(),
+            );
     }
 }
 
 // fn setup(mut commands: Commands) {}
 
+fn spawn_light_glow(
+    trigger: Trigger<OnAdd, Light>,
+    lights: Query<&Light>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let Ok(light) = lights.get(trigger.target()) else {
+        return;
+    };
+    let glow = (
+        LightGlow,
+        Mesh2d(meshes.add(Circle::new(light.glow_radius))),
+        MeshMaterial2d(materials.add(ColorMaterial::from_color(GLOW_COLOR.with_alpha(0.0)))),
+        Transform::from_translation(Vec3::Z * GLOW_Z),
+        Visibility::Hidden,
+    );
+    commands.entity(trigger.target()).with_children(|parent| {
+        parent.spawn(glow);
+    });
+}
+
+fn light_switch_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    cursor: GridCursor,
+    tile_index: Res<TileIndex>,
+    mut lights: Query<&mut Light>,
+) {
+    if !keys.just_pressed(LIGHT_SWITCH_KEY) {
+        return;
+    }
+    let Some(mut light) = cursor
+        .grid_position()
+        .and_then(|pos| tile_index.item(pos))
+        .and_then(|item| lights.get_mut(item).ok())
+    else {
+        return;
+    };
+
+    light.switched_on = !light.switched_on;
+    println!(
+        "Light switched {}",
+        if light.switched_on {
+            "on"
+        } else {
+            "to automatic"
+        }
+    );
+}
+
+/// Unlit lights draw nothing, so the network load follows the day
+fn update_light_demand(clock: Res<WorldClock>, mut lights: Query<(&Light, &mut PowerConsumer)>) {
+    for (light, mut power_consumer) in lights.iter_mut() {
+        let demand = if light.is_lit(&clock) {
+            light.demand
+        } else {
+            0.0
+        };
+        // Only write on change so idle lights don't re-dirty their network
+        if power_consumer.demand != demand {
+            power_consumer.demand = demand;
+        }
+    }
+}
+
+/// Recolours lights only when their power or glow changes, so steady lights
+/// leave their materials alone
+#[allow(clippy::type_complexity)]
 fn update_light_visuals(
-    lights: Query<(&PowerConsumer, &Material2dHandle), With<Light>>,
+    lights: Query<
+        (&PowerConsumer, &Material2dHandle, &Children),
+        (With<Light>, Or<(Changed<PowerConsumer>, Changed<Children>)>),
+    >,
+    mut glows: Query<(&MeshMaterial2d<ColorMaterial>, &mut Visibility), With<LightGlow>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    for (power_consumer, material_handle) in lights.iter() {
+    for (power_consumer, material_handle, children) in lights.iter() {
+        // Dim lights proportionally when the network is overdrawn
+        let brightness = if power_consumer.demand > 0.0 {
+            power_consumer.satisfaction
+        } else {
+            0.0
+        };
         if let Some(material) = materials.get_mut(&material_handle.0) {
-            if power_consumer.powered {
-                material.color = Color::WHITE;
+            material.color = Color::from(GRAY).mix(&Color::WHITE, brightness);
+        }
+
+        for child in children.iter() {
+            let Ok((glow_material, mut visibility)) = glows.get_mut(child) else {
+                continue;
+            };
+            *visibility = if brightness > 0.0 {
+                Visibility::Inherited
             } else {
-                material.color = Color::from(GRAY);
+                Visibility::Hidden
+            };
+            if let Some(material) = materials.get_mut(&glow_material.0) {
+                material.color = GLOW_COLOR.with_alpha(GLOW_ALPHA * brightness);
             }
         }
     }
 }
-
-pub fn spawn_light(
-    commands: &mut Commands,
-    pos: GridPosition,
-    mut meshes: ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<ColorMaterial>>,
-) -> Entity {
-    let light_material_handle = materials.add(ColorMaterial::from_color(GREY));
-    let light_size = 10.0;
-
-    commands
-        .spawn((
-            Name::new("Light"),
-            Light,
-            Mesh2d(meshes.add(Rectangle::new(light_size, light_size))),
-            Material2dHandle(light_material_handle.clone()),
-            MeshMaterial2d(light_material_handle.clone()),
-            Transform::from_translation(grid::grid_to_world(pos) + Vec3::Z),
-            pos,
-            // Single connection point
-            ConnectionPoint::new(1),
-            PowerConsumer::default(),
-        ))
-        .id()
-}
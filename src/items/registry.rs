@@ -0,0 +1,239 @@
+use crate::items::battery::Battery;
+use crate::items::breaker::Breaker;
+use crate::items::definition::{
+    ConsumerDefinition, GateDefinition, ItemDefinition, ItemId, ItemShape, SourceDefinition,
+};
+use crate::items::generator::Generator;
+use crate::items::light::Light;
+use crate::items::power_pole::PowerPole;
+use crate::items::relay::Relay;
+use crate::items::solar_panel::SolarPanel;
+use crate::items::switch::Switch;
+use crate::items::wind_turbine::WindTurbine;
+use crate::power::power_consumer::PowerConsumer;
+use crate::power::power_gate::PowerGate;
+use crate::power::power_source::PowerSource;
+use crate::power::power_storage::PowerStorage;
+use crate::wire_system::ConnectionPoint;
+use crate::wire_system::port::{Facing, Ports};
+use crate::world::grid::{GridConfig, GridPosition, Material2dHandle};
+use bevy::asset::io::file::FileAssetReader;
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Directory, relative to the project root, that item definitions are read from
+const ITEMS_DIR: &str = "assets/items";
+
+/// Marks a placed item and remembers which definition it was built from
+#[derive(Component, Clone)]
+pub struct Item(pub ItemId);
+
+#[derive(Resource, Default)]
+pub struct ItemRegistry {
+    // Sorted by hotkey, then id, so tool order is stable
+    definitions: Vec<ItemDefinition>,
+    index: HashMap<ItemId, usize>,
+}
+
+impl ItemRegistry {
+    pub fn load() -> Self {
+        Self::load_from_dir(&FileAssetReader::get_base_path().join(ITEMS_DIR))
+    }
+
+    pub fn load_from_dir(dir: &Path) -> Self {
+        let mut registry = Self::default();
+
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                println!("Could not read item definitions from {:?}: {}", dir, err);
+                return registry;
+            }
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "ron") {
+                continue;
+            }
+            let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+
+            let definition = fs::read_to_string(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|text| {
+                    ron::from_str::<ItemDefinition>(&text).map_err(|err| err.to_string())
+                });
+
+            match definition {
+                Ok(mut definition) => {
+                    definition.id = ItemId::new(stem);
+                    registry.definitions.push(definition);
+                }
+                Err(err) => println!("Skipping item definition {:?}: {}", path, err),
+            }
+        }
+
+        // Keyboard order: digit 0 sits after 9, so it mustn't take the first slot
+        registry.definitions.sort_by_key(|definition| {
+            let key = match definition.hotkey {
+                Some(0) => 10,
+                Some(digit) => digit,
+                None => u8::MAX,
+            };
+            (key, definition.id.clone())
+        });
+        registry.index = registry
+            .definitions
+            .iter()
+            .enumerate()
+            .map(|(index, definition)| (definition.id.clone(), index))
+            .collect();
+
+        println!("Loaded {} item definitions", registry.definitions.len());
+        registry
+    }
+
+    pub fn get(&self, id: &ItemId) -> Option<&ItemDefinition> {
+        self.index.get(id).map(|index| &self.definitions[*index])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ItemDefinition> {
+        self.definitions.iter()
+    }
+
+    pub fn first(&self) -> Option<&ItemDefinition> {
+        self.definitions.first()
+    }
+
+    /// The definition after `id` in tool order, wrapping around
+    pub fn after(&self, id: &ItemId) -> Option<&ItemDefinition> {
+        let next = self.index.get(id).map_or(0, |index| index + 1);
+        self.definitions
+            .get(next)
+            .or_else(|| self.definitions.first())
+    }
+}
+
+/// Colour an item is drawn in, white if its definition has an invalid one
+pub fn item_color(definition: &ItemDefinition) -> Color {
+    Srgba::hex(&definition.color)
+        .unwrap_or_else(|_| {
+            println!(
+                "Invalid color {:?} for item {}",
+                definition.color, definition.id
+            );
+            Srgba::WHITE
+        })
+        .into()
+}
+
+pub fn item_mesh(shape: &ItemShape, meshes: &mut Assets<Mesh>) -> Handle<Mesh> {
+    match *shape {
+        ItemShape::Circle { radius } => meshes.add(Circle::new(radius)),
+        ItemShape::Rectangle { width, height } => meshes.add(Rectangle::new(width, height)),
+        ItemShape::Triangle { size } => meshes.add(Triangle2d::new(
+            Vec2::Y * size,
+            Vec2::new(-size, -size),
+            Vec2::new(size, -size),
+        )),
+    }
+}
+
+/// Spawns any registered item on `pos`, attaching the components its definition asks for
+pub fn spawn_item(
+    commands: &mut Commands,
+    definition: &ItemDefinition,
+    pos: GridPosition,
+    facing: Facing,
+    config: &GridConfig,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+) -> Entity {
+    let material_handle = materials.add(ColorMaterial::from_color(item_color(definition)));
+    let mesh = item_mesh(&definition.shape, meshes);
+
+    let footprint = facing.rotate_footprint(definition.footprint);
+    let mut item = commands.spawn((
+        Name::new(definition.name.clone()),
+        Item(definition.id.clone()),
+        Mesh2d(mesh),
+        Material2dHandle(material_handle.clone()),
+        MeshMaterial2d(material_handle),
+        // Render above the tiles, centred over the whole footprint
+        Transform::from_translation(config.footprint_center(pos, footprint) + Vec3::Z)
+            .with_rotation(facing.rotation()),
+        pos,
+        footprint,
+        facing,
+        ConnectionPoint::new(definition.max_connections, definition.reach),
+    ));
+
+    if !definition.ports.is_empty() {
+        item.insert(Ports::new(&definition.ports, facing));
+    }
+
+    if definition.pole {
+        item.insert(PowerPole);
+    }
+
+    match definition.source {
+        Some(SourceDefinition::Generator {
+            fuel,
+            fuel_type,
+            tank,
+            max_output,
+            spin_up,
+            spin_down,
+        }) => {
+            let mut generator = Generator::new(fuel_type, fuel, tank, max_output);
+            generator.spin_up = spin_up;
+            generator.spin_down = spin_down;
+            item.insert((generator, PowerSource::default()));
+        }
+        Some(SourceDefinition::Solar { peak_output }) => {
+            item.insert((SolarPanel { peak_output }, PowerSource::default()));
+        }
+        Some(SourceDefinition::Wind { rated_output }) => {
+            item.insert((WindTurbine { rated_output }, PowerSource::default()));
+        }
+        None => {}
+    }
+
+    if let Some(ConsumerDefinition::Light {
+        demand,
+        glow_radius,
+    }) = definition.consumer
+    {
+        item.insert((Light::new(demand, glow_radius), PowerConsumer::new(demand)));
+    }
+
+    if let Some(storage) = &definition.storage {
+        item.insert((
+            Battery,
+            PowerStorage::new(
+                storage.capacity,
+                storage.max_charge_rate,
+                storage.max_discharge_rate,
+            ),
+        ));
+    }
+
+    match &definition.gate {
+        Some(GateDefinition::Switch) => {
+            item.insert((Switch, PowerGate::default()));
+        }
+        Some(GateDefinition::Relay(condition)) => {
+            item.insert((Relay::new(*condition), PowerGate::default()));
+        }
+        Some(GateDefinition::Breaker) => {
+            item.insert((Breaker, PowerGate::default()));
+        }
+        None => {}
+    }
+
+    item.id()
+}
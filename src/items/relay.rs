@@ -0,0 +1,293 @@
+use crate::items::switch::TOGGLE_BUTTON;
+use crate::power::power::power_propagation_system;
+use crate::power::power_gate::PowerGate;
+use crate::power::power_network::{PowerNetwork, PowerNetworks};
+use crate::power::power_storage::PowerStorage;
+use crate::world::clock::WorldClock;
+use crate::world::grid::GridCursor;
+use crate::world::tile_index::TileIndex;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+pub struct RelayPlugin;
+
+impl Plugin for RelayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                relay_condition_input,
+                // Judged on this frame's solution, so a relay that just closed
+                // isn't tripped again by numbers from before it closed
+                update_relays.after(power_propagation_system),
+            ),
+        );
+    }
+}
+
+/// Seconds an opened satisfaction relay waits before closing to try again
+const RECLOSE_DELAY: f32 = 5.0;
+/// Seconds a reclosed satisfaction relay stays closed whatever it sees, so
+/// generators behind it can spin back up
+const RECLOSE_HOLD: f32 = 2.0;
+
+/// What a relay watches to decide whether it's closed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RelayCondition {
+    /// Opens when its network couldn't cover `min` of what's demanded even
+    /// with every generator ramped up, shedding the load behind it, then
+    /// recloses after a delay to retry
+    Satisfaction { min: f32 },
+    /// Closed from `from` to `to` as fractions of the day, wrapping past midnight
+    TimeOfDay { from: f32, to: f32 },
+    /// Closed while the batteries in its network are at least `min` charged
+    BatteryCharge { min: f32 },
+}
+
+impl RelayCondition {
+    /// Cycles through a preset of each condition
+    pub fn next(self) -> Self {
+        match self {
+            RelayCondition::Satisfaction { .. } => RelayCondition::TimeOfDay {
+                from: 0.75,
+                to: 0.25,
+            },
+            RelayCondition::TimeOfDay { .. } => RelayCondition::BatteryCharge { min: 0.5 },
+            RelayCondition::BatteryCharge { .. } => RelayCondition::Satisfaction { min: 0.9 },
+        }
+    }
+}
+
+impl fmt::Display for RelayCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RelayCondition::Satisfaction { min } => {
+                write!(f, "network satisfaction at least {:.0}%", min * 100.0)
+            }
+            RelayCondition::TimeOfDay { from, to } => {
+                write!(f, "time of day from {:.2} to {:.2}", from, to)
+            }
+            RelayCondition::BatteryCharge { min } => {
+                write!(f, "battery charge at least {:.0}%", min * 100.0)
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Relay {
+    pub condition: RelayCondition,
+    reclose: Timer,
+    hold: Timer,
+}
+
+impl Relay {
+    pub fn new(condition: RelayCondition) -> Self {
+        Self {
+            condition,
+            reclose: Timer::from_seconds(RECLOSE_DELAY, TimerMode::Once),
+            hold: Timer::from_seconds(RECLOSE_HOLD, TimerMode::Once),
+        }
+    }
+}
+
+/// Fraction of its reachable demand a network could cover once its generators
+/// have ramped up; a network with nothing to power counts as covered
+fn coverage(network: &PowerNetwork) -> f32 {
+    if network.reachable_demand <= 0.0 {
+        1.0
+    } else {
+        (network.capacity / network.reachable_demand).clamp(0.0, 1.0)
+    }
+}
+
+fn update_relays(
+    time: Res<Time>,
+    clock: Res<WorldClock>,
+    networks: Res<PowerNetworks>,
+    storage: Query<&PowerStorage>,
+    mut relays: Query<(Entity, &mut Relay, &mut PowerGate)>,
+) {
+    for (entity, mut relay, mut gate) in relays.iter_mut() {
+        let network = networks.network_of(entity).and_then(|id| networks.get(id));
+
+        let closed = match relay.condition {
+            RelayCondition::Satisfaction { min } => {
+                if gate.closed {
+                    let holding = !relay.hold.tick(time.delta()).finished();
+                    let satisfied =
+                        holding || network.is_none_or(|network| coverage(network) >= min);
+                    if !satisfied {
+                        relay.reclose.reset();
+                    }
+                    satisfied
+                } else {
+                    let reclosing = relay.reclose.tick(time.delta()).finished();
+                    if reclosing {
+                        relay.hold.reset();
+                    }
+                    reclosing
+                }
+            }
+            RelayCondition::TimeOfDay { from, to } => {
+                let now = clock.time_of_day();
+                if from <= to {
+                    now >= from && now < to
+                } else {
+                    now >= from || now < to
+                }
+            }
+            RelayCondition::BatteryCharge { min } => {
+                let (charge, capacity) = network
+                    .into_iter()
+                    .flat_map(|network| network.members.iter())
+                    .filter_map(|member| storage.get(*member).ok())
+                    .fold((0.0, 0.0), |(charge, capacity), unit| {
+                        (charge + unit.charge, capacity + unit.capacity)
+                    });
+                capacity > 0.0 && charge / capacity >= min
+            }
+        };
+
+        // Only write on change so a steady relay doesn't re-dirty its network
+        if gate.closed != closed {
+            gate.closed = closed;
+            println!(
+                "Relay {} ({})",
+                if closed { "closed" } else { "opened" },
+                relay.condition
+            );
+        }
+    }
+}
+
+/// Clicking a relay cycles what it watches
+fn relay_condition_input(
+    buttons: Res<ButtonInput<MouseButton>>,
+    cursor: GridCursor,
+    tile_index: Res<TileIndex>,
+    mut relays: Query<&mut Relay>,
+) {
+    if !buttons.just_pressed(TOGGLE_BUTTON) {
+        return;
+    }
+    let Some(mut relay) = cursor
+        .grid_position()
+        .and_then(|pos| tile_index.item(pos))
+        .and_then(|item| relays.get_mut(item).ok())
+    else {
+        return;
+    };
+
+    relay.condition = relay.condition.next();
+    relay.reclose.reset();
+    println!("Relay now closes on {}", relay.condition);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::power::power_consumer::PowerConsumer;
+    use crate::power::power_source::PowerSource;
+    use crate::wire_system::{Wire, WireKind};
+    use bevy::ecs::system::RunSystemOnce;
+    use std::time::Duration;
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.insert_resource(Time::<()>::default());
+        world.init_resource::<WorldClock>();
+        world.init_resource::<PowerNetworks>();
+        world
+    }
+
+    fn wire(world: &mut World, from: Entity, to: Entity) {
+        let wire = world
+            .spawn(Wire {
+                from,
+                to,
+                kind: WireKind::HighVoltage,
+                from_port: None,
+                to_port: None,
+            })
+            .id();
+        world
+            .resource_mut::<PowerNetworks>()
+            .add_edge(wire, from, to);
+    }
+
+    /// Advances time by `seconds`, re-solves the relay's network and judges it
+    fn step(world: &mut World, relay: Entity, seconds: f32) -> bool {
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(seconds));
+        world.resource_mut::<PowerNetworks>().mark_dirty(relay);
+        world.run_system_once(power_propagation_system).unwrap();
+        world.run_system_once(update_relays).unwrap();
+        world.get::<PowerGate>(relay).unwrap().closed
+    }
+
+    fn satisfaction_relay(world: &mut World) -> Entity {
+        world
+            .spawn((
+                Relay::new(RelayCondition::Satisfaction { min: 0.9 }),
+                PowerGate::default(),
+            ))
+            .id()
+    }
+
+    #[test]
+    fn relays_with_nothing_to_power_stay_closed() {
+        let mut world = world();
+        let relay = satisfaction_relay(&mut world);
+        world.resource_mut::<PowerNetworks>().add_node(relay);
+
+        for _ in 0..10 {
+            assert!(step(&mut world, relay, 1.0));
+        }
+    }
+
+    #[test]
+    fn relays_behind_a_generator_stay_closed_after_reclosing() {
+        let mut world = world();
+        // Spun down to its reserve while the relay was open
+        let generator = world
+            .spawn(PowerSource {
+                output: 10.0,
+                capacity: 100.0,
+                ..default()
+            })
+            .id();
+        let relay = satisfaction_relay(&mut world);
+        world.get_mut::<PowerGate>(relay).unwrap().closed = false;
+        let consumer = world.spawn(PowerConsumer::new(80.0)).id();
+        wire(&mut world, generator, relay);
+        wire(&mut world, relay, consumer);
+
+        assert!(!step(&mut world, relay, 0.0));
+        assert!(step(&mut world, relay, RECLOSE_DELAY));
+        for _ in 0..10 {
+            assert!(step(&mut world, relay, 0.5));
+        }
+    }
+
+    #[test]
+    fn relays_open_when_even_a_ramped_up_network_falls_short() {
+        let mut world = world();
+        let generator = world
+            .spawn(PowerSource {
+                output: 100.0,
+                capacity: 100.0,
+                ..default()
+            })
+            .id();
+        let relay = satisfaction_relay(&mut world);
+        let consumer = world.spawn(PowerConsumer::new(150.0)).id();
+        wire(&mut world, generator, relay);
+        wire(&mut world, relay, consumer);
+
+        assert!(step(&mut world, relay, RECLOSE_HOLD / 2.0));
+        assert!(!step(&mut world, relay, RECLOSE_HOLD));
+    }
+}
@@ -0,0 +1,29 @@
+use crate::power::power_source::PowerSource;
+use crate::world::clock::WorldClock;
+use bevy::prelude::*;
+
+pub struct SolarPanelPlugin;
+
+impl Plugin for SolarPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, update_solar_output);
+    }
+}
+
+/// Fuel-less source whose output follows the sun
+#[derive(Component)]
+pub struct SolarPanel {
+    /// Wattage at noon
+    pub peak_output: f32,
+}
+
+fn update_solar_output(clock: Res<WorldClock>, mut panels: Query<(&SolarPanel, &mut PowerSource)>) {
+    let daylight = clock.daylight();
+    for (panel, mut power_source) in panels.iter_mut() {
+        let output = panel.peak_output * daylight;
+        // Only write on change so panels at night don't re-dirty their network
+        if power_source.output != output {
+            power_source.output = output;
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use crate::power::power_gate::PowerGate;
+use crate::world::grid::{GridCursor, Material2dHandle};
+use crate::world::tile_index::TileIndex;
+use bevy::color::palettes::basic::{GREEN, RED};
+use bevy::prelude::*;
+
+pub struct SwitchPlugin;
+
+/// Clicking a switch with this button flips it
+pub const TOGGLE_BUTTON: MouseButton = MouseButton::Middle;
+
+#[derive(Component)]
+pub struct Switch;
+
+impl Plugin for SwitchPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (switch_toggle_input, update_gate_visuals));
+    }
+}
+
+fn switch_toggle_input(
+    buttons: Res<ButtonInput<MouseButton>>,
+    cursor: GridCursor,
+    tile_index: Res<TileIndex>,
+    mut switches: Query<&mut PowerGate, With<Switch>>,
+) {
+    if !buttons.just_pressed(TOGGLE_BUTTON) {
+        return;
+    }
+    let Some(mut gate) = cursor
+        .grid_position()
+        .and_then(|pos| tile_index.item(pos))
+        .and_then(|item| switches.get_mut(item).ok())
+    else {
+        return;
+    };
+
+    gate.closed = !gate.closed;
+    println!("Switch {}", if gate.closed { "closed" } else { "opened" });
+}
+
+/// Closed gates of any kind are green, open ones red
+fn update_gate_visuals(
+    gates: Query<(&PowerGate, &Material2dHandle), Changed<PowerGate>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (gate, material_handle) in gates.iter() {
+        if let Some(material) = materials.get_mut(&material_handle.0) {
+            material.color = Color::from(if gate.closed { GREEN } else { RED });
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use crate::power::power_source::PowerSource;
+use bevy::prelude::*;
+use std::f32::consts::TAU;
+
+pub struct WindTurbinePlugin;
+
+impl Plugin for WindTurbinePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Wind>()
+            .add_systems(Update, update_wind_output);
+    }
+}
+
+/// Wind speed below which turbines don't turn, as a fraction of rated speed
+const CUT_IN_SPEED: f32 = 0.15;
+/// Number of sine waves layered into the wind
+const WIND_WAVES: usize = 4;
+
+/// Fuel-less source whose output follows the wind
+#[derive(Component)]
+pub struct WindTurbine {
+    /// Wattage at or above rated wind speed
+    pub rated_output: f32,
+}
+
+/// Wind shared by every turbine. The same seed always gives the same wind at
+/// the same time, so runs can be reproduced.
+#[derive(Resource)]
+pub struct Wind {
+    /// `(period in seconds, phase)` of each wave making up the wind
+    waves: Vec<(f32, f32)>,
+    /// Seconds the wind has been blowing; unlike the world clock it's never
+    /// rescaled, so changing the day length doesn't jump the wind
+    elapsed: f32,
+}
+
+impl Default for Wind {
+    fn default() -> Self {
+        Self::from_seed(0x5eed)
+    }
+}
+
+impl Wind {
+    pub fn from_seed(seed: u64) -> Self {
+        // Small LCG so the waves don't depend on a random number crate
+        let mut state = seed;
+        let mut next = move || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 40) as f32 / (1u64 << 24) as f32
+        };
+        let waves = (0..WIND_WAVES)
+            .map(|index| {
+                // Slower waves for the weather, faster ones for gusts
+                let period = 20.0 * (index + 1) as f32 * (0.5 + next());
+                (period, next() * TAU)
+            })
+            .collect();
+        Self {
+            waves,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Wind speed at `time` seconds, as a fraction of rated speed from 0 to 1
+    pub fn speed(&self, time: f32) -> f32 {
+        let sum: f32 = self
+            .waves
+            .iter()
+            .map(|(period, phase)| (time / period * TAU + phase).sin())
+            .sum();
+        (0.5 + 0.5 * sum / self.waves.len() as f32).clamp(0.0, 1.0)
+    }
+}
+
+/// Fraction of rated output produced at wind `speed`: nothing below cut-in,
+/// then rising with the cube of the speed
+fn power_curve(speed: f32) -> f32 {
+    if speed < CUT_IN_SPEED {
+        return 0.0;
+    }
+    speed.powi(3).min(1.0)
+}
+
+fn update_wind_output(
+    time: Res<Time>,
+    mut wind: ResMut<Wind>,
+    mut turbines: Query<(&WindTurbine, &mut PowerSource)>,
+) {
+    wind.elapsed += time.delta_secs();
+    let fraction = power_curve(wind.speed(wind.elapsed));
+    for (turbine, mut power_source) in turbines.iter_mut() {
+        let output = turbine.rated_output * fraction;
+        if power_source.output != output {
+            power_source.output = output;
+        }
+    }
+}
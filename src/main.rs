@@ -1,5 +1,6 @@
 mod items;
 mod power;
+mod save;
 mod tools;
 mod ui;
 mod wire_system;
@@ -7,12 +8,15 @@ mod world;
 
 use crate::items::ItemsPlugin;
 use crate::power::power::PowerPlugin;
+use crate::save::SavePlugin;
 use crate::ui::UiPlugin;
 use crate::wire_system::WireSystemPlugin;
 use crate::world::camera::CameraPlugin;
+use crate::world::clock::ClockPlugin;
 use bevy::prelude::*;
 use tools::build_tool::BuildToolPlugin;
 use world::grid::GridPlugin;
+use world::history::HistoryPlugin;
 
 fn main() {
     App::new()
@@ -25,7 +29,13 @@ fn main() {
         }))
         .add_plugins(ItemsPlugin)
         .add_plugins((GridPlugin, BuildToolPlugin, WireSystemPlugin, PowerPlugin))
-        .add_plugins((UiPlugin, CameraPlugin))
+        .add_plugins((
+            UiPlugin,
+            CameraPlugin,
+            ClockPlugin,
+            SavePlugin,
+            HistoryPlugin,
+        ))
         // .add_systems(Startup, setup)
         .add_systems(Update, keyboard_input)
         .run();
@@ -1,3 +1,8 @@
+pub mod flow;
+pub mod overload;
 pub mod power;
 pub mod power_consumer;
+pub mod power_gate;
+pub mod power_network;
 pub mod power_source;
+pub mod power_storage;
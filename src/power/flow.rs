@@ -0,0 +1,241 @@
+use std::collections::VecDeque;
+
+/// Residual capacity below which an edge counts as saturated
+const EPSILON: f32 = 1e-4;
+
+struct FlowEdge {
+    to: usize,
+    capacity: f32,
+    flow: f32,
+}
+
+/// Max-flow graph over watts. Edges are stored in pairs so `edge ^ 1` is always
+/// the reverse of `edge`, which lets augmenting paths push flow back.
+#[derive(Default)]
+pub struct FlowGraph {
+    edges: Vec<FlowEdge>,
+    adjacency: Vec<Vec<usize>>,
+}
+
+impl FlowGraph {
+    pub fn add_node(&mut self) -> usize {
+        self.adjacency.push(Vec::new());
+        self.adjacency.len() - 1
+    }
+
+    /// One-way edge; returns its index for reading the flow back later
+    pub fn add_edge(&mut self, from: usize, to: usize, capacity: f32) -> usize {
+        self.add_pair(from, to, capacity, 0.0)
+    }
+
+    /// Edge that carries up to `capacity` in either direction, e.g. a wire
+    pub fn add_undirected_edge(&mut self, a: usize, b: usize, capacity: f32) -> usize {
+        self.add_pair(a, b, capacity, capacity)
+    }
+
+    /// Net flow through `edge` in its forward direction
+    pub fn flow(&self, edge: usize) -> f32 {
+        self.edges[edge].flow
+    }
+
+    /// Pushes as much flow as possible from `source` to `sink` on top of whatever
+    /// already flows, so edges added later can extend an earlier solution.
+    /// Returns the additional flow.
+    pub fn max_flow(&mut self, source: usize, sink: usize) -> f32 {
+        let mut total = 0.0;
+
+        // Edmonds-Karp: repeatedly augment along the shortest residual path
+        while let Some(path) = self.shortest_augmenting_path(source, sink) {
+            let bottleneck = path
+                .iter()
+                .map(|edge| self.residual(*edge))
+                .fold(f32::INFINITY, f32::min);
+
+            for edge in path {
+                self.edges[edge].flow += bottleneck;
+                self.edges[edge ^ 1].flow -= bottleneck;
+            }
+            total += bottleneck;
+        }
+
+        total
+    }
+
+    /// Nodes reachable from any of `starts` along edges with capacity left in
+    /// their direction, regardless of how much flow they carry
+    pub fn reachable(&self, starts: impl IntoIterator<Item = usize>) -> Vec<bool> {
+        let mut visited = vec![false; self.adjacency.len()];
+        let mut queue = VecDeque::new();
+        for start in starts {
+            if !visited[start] {
+                visited[start] = true;
+                queue.push_back(start);
+            }
+        }
+
+        while let Some(node) = queue.pop_front() {
+            for &edge in &self.adjacency[node] {
+                let next = self.edges[edge].to;
+                if !visited[next] && self.edges[edge].capacity > EPSILON {
+                    visited[next] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+        visited
+    }
+
+    fn add_pair(&mut self, from: usize, to: usize, capacity: f32, reverse_capacity: f32) -> usize {
+        let index = self.edges.len();
+        self.edges.push(FlowEdge {
+            to,
+            capacity: capacity.max(0.0),
+            flow: 0.0,
+        });
+        self.edges.push(FlowEdge {
+            to: from,
+            capacity: reverse_capacity.max(0.0),
+            flow: 0.0,
+        });
+        self.adjacency[from].push(index);
+        self.adjacency[to].push(index + 1);
+        index
+    }
+
+    fn residual(&self, edge: usize) -> f32 {
+        self.edges[edge].capacity - self.edges[edge].flow
+    }
+
+    fn shortest_augmenting_path(&self, source: usize, sink: usize) -> Option<Vec<usize>> {
+        let mut via: Vec<Option<usize>> = vec![None; self.adjacency.len()];
+        let mut visited = vec![false; self.adjacency.len()];
+        let mut queue = VecDeque::from([source]);
+        visited[source] = true;
+
+        while let Some(node) = queue.pop_front() {
+            if node == sink {
+                break;
+            }
+            for &edge in &self.adjacency[node] {
+                let next = self.edges[edge].to;
+                if !visited[next] && self.residual(edge) > EPSILON {
+                    visited[next] = true;
+                    via[next] = Some(edge);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        if !visited[sink] {
+            return None;
+        }
+
+        let mut path = Vec::new();
+        let mut node = sink;
+        while let Some(edge) = via[node] {
+            path.push(edge);
+            node = self.edges[edge ^ 1].to;
+        }
+        Some(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flow_is_limited_by_the_narrowest_edge() {
+        let mut graph = FlowGraph::default();
+        let (source, middle, sink) = (graph.add_node(), graph.add_node(), graph.add_node());
+        let supply = graph.add_edge(source, middle, 50.0);
+        let wire = graph.add_edge(middle, sink, 30.0);
+
+        assert_eq!(graph.max_flow(source, sink), 30.0);
+        assert_eq!(graph.flow(supply), 30.0);
+        assert_eq!(graph.flow(wire), 30.0);
+    }
+
+    #[test]
+    fn parallel_paths_add_up() {
+        let mut graph = FlowGraph::default();
+        let (source, a, b, sink) = (
+            graph.add_node(),
+            graph.add_node(),
+            graph.add_node(),
+            graph.add_node(),
+        );
+        graph.add_edge(source, a, 40.0);
+        graph.add_edge(source, b, 40.0);
+        graph.add_edge(a, sink, 25.0);
+        graph.add_edge(b, sink, 25.0);
+
+        assert_eq!(graph.max_flow(source, sink), 50.0);
+    }
+
+    #[test]
+    fn undirected_edges_carry_flow_backwards() {
+        let mut graph = FlowGraph::default();
+        let (source, a, b, sink) = (
+            graph.add_node(),
+            graph.add_node(),
+            graph.add_node(),
+            graph.add_node(),
+        );
+        graph.add_edge(source, b, 20.0);
+        let wire = graph.add_undirected_edge(a, b, 100.0);
+        graph.add_edge(a, sink, 20.0);
+
+        assert_eq!(graph.max_flow(source, sink), 20.0);
+        assert_eq!(graph.flow(wire), -20.0);
+    }
+
+    #[test]
+    fn one_way_edges_block_flow_backwards() {
+        let mut graph = FlowGraph::default();
+        let (source, a, b, sink) = (
+            graph.add_node(),
+            graph.add_node(),
+            graph.add_node(),
+            graph.add_node(),
+        );
+        graph.add_edge(source, b, 20.0);
+        graph.add_edge(a, b, 100.0);
+        graph.add_edge(a, sink, 20.0);
+
+        assert_eq!(graph.max_flow(source, sink), 0.0);
+    }
+
+    #[test]
+    fn max_flow_extends_an_earlier_solution() {
+        let mut graph = FlowGraph::default();
+        let (source, node, sink) = (graph.add_node(), graph.add_node(), graph.add_node());
+        graph.add_edge(source, node, 10.0);
+        let consumer = graph.add_edge(node, sink, 30.0);
+        assert_eq!(graph.max_flow(source, sink), 10.0);
+
+        // Storage joining in later tops up what generation couldn't cover
+        graph.add_edge(source, node, 15.0);
+        assert_eq!(graph.max_flow(source, sink), 15.0);
+        assert_eq!(graph.flow(consumer), 25.0);
+    }
+
+    #[test]
+    fn reachable_skips_edges_without_capacity() {
+        let mut graph = FlowGraph::default();
+        let (a, b, c, d) = (
+            graph.add_node(),
+            graph.add_node(),
+            graph.add_node(),
+            graph.add_node(),
+        );
+        graph.add_undirected_edge(a, b, 100.0);
+        // An open gate
+        graph.add_undirected_edge(b, c, 0.0);
+        // A diode pointing back towards `a`
+        graph.add_edge(d, a, 100.0);
+
+        assert_eq!(graph.reachable([a]), vec![true, true, false, false]);
+        assert_eq!(graph.reachable([d]), vec![true, true, false, true]);
+    }
+}
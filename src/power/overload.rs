@@ -0,0 +1,378 @@
+use crate::power::power::power_propagation_system;
+use crate::power::power_consumer::PowerConsumer;
+use crate::power::power_network::{NetworkId, PowerNetwork, PowerNetworks};
+use crate::wire_system::Wire;
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::fmt;
+
+pub struct OverloadPlugin;
+
+impl Plugin for OverloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OverloadMonitor>()
+            .add_event::<NetworkTripped>()
+            .add_systems(
+                Update,
+                (
+                    (detect_overloads, report_trips)
+                        .chain()
+                        .after(power_propagation_system),
+                    flash_system,
+                ),
+            );
+    }
+}
+
+/// Seconds a network may stay overloaded before it trips
+const TRIP_DELAY: f32 = 3.0;
+/// Seconds between blinks of whatever is blamed for a trip
+const FLASH_INTERVAL: f32 = 0.25;
+/// Slack for float noise in the solver's numbers
+const OVERLOAD_EPSILON: f32 = 0.01;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OverloadCause {
+    /// Consumers want more than generation and storage could give, even with
+    /// every generator ramped up
+    Shortfall { demand: f32, supply: f32 },
+    /// A wire is pushed to its rated throughput and consumers still go short
+    Wire {
+        wire: Entity,
+        flow: f32,
+        rating: f32,
+    },
+}
+
+impl fmt::Display for OverloadCause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OverloadCause::Shortfall { demand, supply } => {
+                write!(f, "demand {:.1}W exceeds supply {:.1}W", demand, supply)
+            }
+            OverloadCause::Wire { flow, rating, .. } => {
+                write!(f, "wire carrying {:.1}W of its {:.1}W rating", flow, rating)
+            }
+        }
+    }
+}
+
+/// Sent when a network stays overloaded long enough to trip
+#[derive(Event, Debug, Clone, Copy)]
+pub struct NetworkTripped {
+    pub network: NetworkId,
+    pub cause: OverloadCause,
+    /// Wire blamed for the trip, if the network has any
+    pub wire: Option<Entity>,
+}
+
+/// Consumer cut off by a trip; it draws nothing until its network is reset
+#[derive(Component)]
+pub struct Tripped;
+
+/// Wire or item blamed for a trip, blinking until its network is reset
+#[derive(Component)]
+pub struct Flashing(Timer);
+
+impl Default for Flashing {
+    fn default() -> Self {
+        Self(Timer::from_seconds(FLASH_INTERVAL, TimerMode::Repeating))
+    }
+}
+
+/// How long each network has been overloaded without a break
+#[derive(Resource, Default)]
+pub struct OverloadMonitor {
+    overloaded_for: HashMap<NetworkId, f32>,
+}
+
+/// Why `network` is overloaded right now, if it is
+pub fn overload_cause(network: &PowerNetwork, wires: &Query<&Wire>) -> Option<OverloadCause> {
+    // Generators still ramping count at full output, and consumers no source
+    // can reach don't count at all
+    if network.reachable_demand > network.capacity + OVERLOAD_EPSILON {
+        return Some(OverloadCause::Shortfall {
+            demand: network.reachable_demand,
+            supply: network.capacity,
+        });
+    }
+    let delivered = network.satisfaction * network.demand;
+    if delivered >= network.reachable_demand - OVERLOAD_EPSILON {
+        return None;
+    }
+
+    network
+        .wire_flows
+        .iter()
+        .filter_map(|(wire, flow)| {
+            let rating = wires.get(*wire).ok()?.kind.throughput();
+            (*flow > 0.0 && *flow >= rating - OVERLOAD_EPSILON).then_some(OverloadCause::Wire {
+                wire: *wire,
+                flow: *flow,
+                rating,
+            })
+        })
+        .next()
+}
+
+/// Wire carrying the largest share of its rating, the one blamed for a trip
+fn busiest_wire(network: &PowerNetwork, wires: &Query<&Wire>) -> Option<Entity> {
+    network
+        .wire_flows
+        .iter()
+        .filter_map(|(wire, flow)| Some((*wire, flow / wires.get(*wire).ok()?.kind.throughput())))
+        .max_by(|(a_wire, a), (b_wire, b)| a.total_cmp(b).then(b_wire.cmp(a_wire)))
+        .map(|(wire, _)| wire)
+}
+
+fn detect_overloads(
+    time: Res<Time>,
+    networks: Res<PowerNetworks>,
+    mut monitor: ResMut<OverloadMonitor>,
+    wires: Query<&Wire>,
+    consumers: Query<Entity, (With<PowerConsumer>, Without<Tripped>)>,
+    mut events: EventWriter<NetworkTripped>,
+    mut commands: Commands,
+) {
+    let dt = time.delta_secs();
+    // Networks that vanished or recovered start over
+    monitor.overloaded_for.retain(|id, _| {
+        networks
+            .get(*id)
+            .is_some_and(|network| overload_cause(network, &wires).is_some())
+    });
+
+    for (id, network) in networks.iter() {
+        let Some(cause) = overload_cause(network, &wires) else {
+            continue;
+        };
+        let overloaded_for = monitor.overloaded_for.entry(*id).or_default();
+        *overloaded_for += dt;
+        if *overloaded_for < TRIP_DELAY {
+            continue;
+        }
+        monitor.overloaded_for.remove(id);
+
+        for member in network.members.iter() {
+            if consumers.contains(*member) {
+                commands.entity(*member).try_insert(Tripped);
+            }
+        }
+
+        let wire = match cause {
+            OverloadCause::Wire { wire, .. } => Some(wire),
+            OverloadCause::Shortfall { .. } => busiest_wire(network, &wires),
+        };
+        // The wire blinks along with the poles or items at its ends
+        if let Some((wire, blamed)) = wire.and_then(|wire| Some((wire, wires.get(wire).ok()?))) {
+            for entity in [wire, blamed.from, blamed.to] {
+                commands.entity(entity).try_insert(Flashing::default());
+            }
+        }
+
+        events.write(NetworkTripped {
+            network: *id,
+            cause,
+            wire,
+        });
+    }
+}
+
+fn report_trips(mut events: EventReader<NetworkTripped>) {
+    for event in events.read() {
+        match event.wire {
+            Some(wire) => println!("Network tripped: {} (wire {} flashing)", event.cause, wire),
+            None => println!("Network tripped: {}", event.cause),
+        }
+    }
+}
+
+fn flash_system(
+    time: Res<Time>,
+    mut flashing: Query<(&mut Flashing, &mut Visibility)>,
+    mut removed: RemovedComponents<Flashing>,
+    mut visibilities: Query<&mut Visibility, Without<Flashing>>,
+) {
+    for (mut flash, mut visibility) in flashing.iter_mut() {
+        if flash.0.tick(time.delta()).just_finished() {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Inherited,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+
+    // Whatever stopped flashing comes back into view
+    for entity in removed.read() {
+        if let Ok(mut visibility) = visibilities.get_mut(entity) {
+            *visibility = Visibility::Inherited;
+        }
+    }
+}
+
+/// Clears a tripped network so its consumers draw power again
+pub fn reset_network(world: &mut World, network: NetworkId) {
+    let Some(members) = world
+        .resource::<PowerNetworks>()
+        .get(network)
+        .map(|network| network.members.clone())
+    else {
+        return;
+    };
+    let wires: Vec<Entity> = world
+        .resource::<PowerNetworks>()
+        .wires_in(network)
+        .into_iter()
+        .map(|(wire, _, _)| wire)
+        .collect();
+
+    for entity in members.into_iter().chain(wires) {
+        if let Ok(mut entity) = world.get_entity_mut(entity) {
+            entity.remove::<(Tripped, Flashing)>();
+        }
+    }
+    world
+        .resource_mut::<OverloadMonitor>()
+        .overloaded_for
+        .remove(&network);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::power::power_gate::PowerGate;
+    use crate::power::power_source::PowerSource;
+    use crate::wire_system::WireKind;
+    use bevy::ecs::system::RunSystemOnce;
+    use std::time::Duration;
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.insert_resource(Time::<()>::default());
+        world.init_resource::<PowerNetworks>();
+        world.init_resource::<OverloadMonitor>();
+        world.init_resource::<Events<NetworkTripped>>();
+        world
+    }
+
+    fn source(world: &mut World, output: f32, capacity: f32) -> Entity {
+        world
+            .spawn(PowerSource {
+                output,
+                capacity,
+                ..default()
+            })
+            .id()
+    }
+
+    fn wire(world: &mut World, from: Entity, to: Entity) -> Entity {
+        let wire = world
+            .spawn(Wire {
+                from,
+                to,
+                kind: WireKind::HighVoltage,
+                from_port: None,
+                to_port: None,
+            })
+            .id();
+        world
+            .resource_mut::<PowerNetworks>()
+            .add_edge(wire, from, to);
+        wire
+    }
+
+    /// Solves the network `member` is in and returns why it's overloaded
+    fn solve(world: &mut World, member: Entity) -> Option<OverloadCause> {
+        world.run_system_once(power_propagation_system).unwrap();
+        world
+            .run_system_once(move |networks: Res<PowerNetworks>, wires: Query<&Wire>| {
+                let network = networks.get(networks.network_of(member)?)?;
+                overload_cause(network, &wires)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn demand_beyond_capacity_is_a_shortfall() {
+        let mut world = world();
+        let generator = source(&mut world, 100.0, 100.0);
+        let consumer = world.spawn(PowerConsumer::new(150.0)).id();
+        wire(&mut world, generator, consumer);
+
+        assert_eq!(
+            solve(&mut world, consumer),
+            Some(OverloadCause::Shortfall {
+                demand: 150.0,
+                supply: 100.0
+            })
+        );
+    }
+
+    #[test]
+    fn generators_still_ramping_are_not_a_shortfall() {
+        let mut world = world();
+        let generator = source(&mut world, 20.0, 100.0);
+        let consumer = world.spawn(PowerConsumer::new(80.0)).id();
+        wire(&mut world, generator, consumer);
+
+        assert_eq!(solve(&mut world, consumer), None);
+        assert_eq!(
+            world.get::<PowerConsumer>(consumer).unwrap().satisfaction,
+            0.25
+        );
+    }
+
+    #[test]
+    fn consumers_behind_an_open_gate_are_not_a_shortfall() {
+        let mut world = world();
+        let generator = source(&mut world, 100.0, 100.0);
+        let gate = world.spawn(PowerGate { closed: false }).id();
+        let consumer = world.spawn(PowerConsumer::new(500.0)).id();
+        wire(&mut world, generator, gate);
+        wire(&mut world, gate, consumer);
+
+        assert_eq!(solve(&mut world, consumer), None);
+        let networks = world.resource::<PowerNetworks>();
+        let network = networks
+            .get(networks.network_of(consumer).unwrap())
+            .unwrap();
+        assert_eq!(network.reachable_demand, 0.0);
+    }
+
+    #[test]
+    fn tripped_consumers_are_unpowered() {
+        let mut world = world();
+        let generator = source(&mut world, 100.0, 100.0);
+        let consumer = world.spawn((PowerConsumer::new(50.0), Tripped)).id();
+        wire(&mut world, generator, consumer);
+
+        assert_eq!(solve(&mut world, consumer), None);
+        let consumer = world.get::<PowerConsumer>(consumer).unwrap();
+        assert_eq!(consumer.satisfaction, 0.0);
+        assert!(!consumer.powered);
+    }
+
+    #[test]
+    fn sustained_overloads_trip_their_consumers() {
+        let mut world = world();
+        let generator = source(&mut world, 100.0, 100.0);
+        let consumer = world.spawn(PowerConsumer::new(150.0)).id();
+        let blamed = wire(&mut world, generator, consumer);
+        world.run_system_once(power_propagation_system).unwrap();
+
+        let trip_after = |seconds: f32, world: &mut World| {
+            world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs_f32(seconds));
+            world.run_system_once(detect_overloads).unwrap();
+            world.entity(consumer).contains::<Tripped>()
+        };
+        assert!(!trip_after(TRIP_DELAY / 2.0, &mut world));
+        assert!(trip_after(TRIP_DELAY / 2.0, &mut world));
+
+        let events = world.resource::<Events<NetworkTripped>>();
+        let event = events.iter_current_update_events().next().unwrap();
+        assert_eq!(event.wire, Some(blamed));
+        assert!(world.entity(blamed).contains::<Flashing>());
+    }
+}
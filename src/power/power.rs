@@ -1,82 +1,353 @@
+use crate::power::flow::FlowGraph;
+use crate::power::overload::{OverloadPlugin, Tripped};
 use crate::power::power_consumer::PowerConsumer;
+use crate::power::power_gate::PowerGate;
+use crate::power::power_network::PowerNetworks;
 use crate::power::power_source::PowerSource;
-use crate::wire_system::{ConnectionPoint, Wire};
+use crate::power::power_storage::PowerStorage;
+use crate::wire_system::port::{PortKind, Ports, port_kind};
+use crate::wire_system::{ConnectionPoint, Wire, tile_distance};
+use crate::world::grid::GridPosition;
 use bevy::prelude::*;
-use std::collections::HashSet;
+use std::collections::HashMap;
 
 pub struct PowerPlugin;
 
 impl Plugin for PowerPlugin {
     fn build(&self, app: &mut App) {
-        app
+        app.add_plugins(OverloadPlugin)
+            .init_resource::<PowerNetworks>()
             // .add_systems(Startup, setup)
-            .add_systems(Update, power_propagation_system);
+            .add_observer(on_connection_point_added)
+            .add_observer(on_connection_point_removed)
+            .add_observer(on_wire_added)
+            .add_observer(on_wire_removed)
+            .add_systems(
+                Update,
+                (mark_changed_networks, power_propagation_system).chain(),
+            );
     }
 }
 
 // fn setup(mut commands: Commands) {}
 
-fn power_propagation_system(
-    mut consumers: Query<(Entity, &mut PowerConsumer)>,
-    sources: Query<(Entity, &PowerSource)>,
+/// Fraction of `demand` that `supply` can cover, clamped to 0.0 - 1.0
+pub fn satisfaction(supply: f32, demand: f32) -> f32 {
+    if demand <= 0.0 {
+        if supply > 0.0 { 1.0 } else { 0.0 }
+    } else {
+        (supply / demand).clamp(0.0, 1.0)
+    }
+}
+
+fn on_connection_point_added(
+    trigger: Trigger<OnAdd, ConnectionPoint>,
+    mut networks: ResMut<PowerNetworks>,
+) {
+    networks.add_node(trigger.target());
+}
+
+fn on_connection_point_removed(
+    trigger: Trigger<OnRemove, ConnectionPoint>,
+    mut networks: ResMut<PowerNetworks>,
+) {
+    networks.remove_node(trigger.target());
+}
+
+fn on_wire_added(
+    trigger: Trigger<OnAdd, Wire>,
     wires: Query<&Wire>,
-    connection_points: Query<&ConnectionPoint>,
+    mut networks: ResMut<PowerNetworks>,
 ) {
-    // Reset all consumers to unpowered
-    for (_, mut consumer) in consumers.iter_mut() {
-        consumer.powered = false;
+    if let Ok(wire) = wires.get(trigger.target()) {
+        networks.add_edge(trigger.target(), wire.from, wire.to);
     }
+}
 
-    // Find all powered sources
-    let powered_sources: Vec<Entity> = sources
-        .iter()
-        .filter_map(
-            |(entity, source)| {
-                if source.powered { Some(entity) } else { None }
-            },
-        )
-        .collect();
-
-    // Propagate power through wire network
-    let mut powered_entities = HashSet::new();
-    for source_entity in powered_sources {
-        powered_entities.insert(source_entity);
-        propagate_power_from(
-            source_entity,
-            &wires,
-            &connection_points,
-            &mut powered_entities,
-        );
-    }
+fn on_wire_removed(trigger: Trigger<OnRemove, Wire>, mut networks: ResMut<PowerNetworks>) {
+    networks.remove_edge(trigger.target());
+}
 
-    // Update consumer power state
-    for (entity, mut consumer) in consumers.iter_mut() {
-        if powered_entities.contains(&entity) {
-            consumer.powered = true;
-        }
+fn mark_changed_networks(
+    mut networks: ResMut<PowerNetworks>,
+    sources: Query<Entity, Changed<PowerSource>>,
+    consumers: Query<Entity, Changed<PowerConsumer>>,
+    gates: Query<Entity, Changed<PowerGate>>,
+    tripped: Query<Entity, Added<Tripped>>,
+    mut reset: RemovedComponents<Tripped>,
+    storage: Query<Entity, With<PowerStorage>>,
+) {
+    // Storage charge levels drift every frame, so their networks always need rebalancing
+    for entity in sources
+        .iter()
+        .chain(consumers.iter())
+        .chain(gates.iter())
+        .chain(tripped.iter())
+        .chain(reset.read())
+        .chain(storage.iter())
+    {
+        networks.mark_dirty(entity);
     }
 }
 
-fn propagate_power_from(
-    from_entity: Entity,
-    wires: &Query<&Wire>,
-    connection_points: &Query<&ConnectionPoint>,
-    powered_entities: &mut HashSet<Entity>,
+#[allow(clippy::too_many_arguments)]
+pub fn power_propagation_system(
+    time: Res<Time>,
+    mut networks: ResMut<PowerNetworks>,
+    mut consumers: Query<&mut PowerConsumer>,
+    mut sources: Query<&mut PowerSource>,
+    mut storage: Query<&mut PowerStorage>,
+    wires: Query<&Wire>,
+    positions: Query<&GridPosition>,
+    ports: Query<&Ports>,
+    gates: Query<&PowerGate>,
+    tripped: Query<(), With<Tripped>>,
 ) {
-    if let Ok(connection_point) = connection_points.get(from_entity) {
-        for &wire_entity in &connection_point.connections {
-            if let Ok(wire) = wires.get(wire_entity) {
-                let target = if wire.from == from_entity {
-                    wire.to
+    let dt = time.delta_secs();
+
+    // Only networks whose topology, sources or consumers changed are rebalanced
+    for id in networks.take_dirty() {
+        let network_wires = networks.wires_in(id);
+        let Some(network) = networks.get_mut(id) else {
+            continue;
+        };
+
+        // Sorted so the flow solution doesn't depend on hash order
+        let mut members: Vec<Entity> = network.members.iter().copied().collect();
+        members.sort();
+
+        let mut graph = FlowGraph::default();
+        let source = graph.add_node();
+        let sink = graph.add_node();
+        let nodes: HashMap<Entity, usize> = members
+            .iter()
+            .map(|entity| (*entity, graph.add_node()))
+            .collect();
+
+        // Wires carry power up to their rated throughput, both ways unless a
+        // port on either end only lets it through in one direction, and not
+        // at all into an open gate
+        let open = |entity: &Entity| gates.get(*entity).is_ok_and(|gate| !gate.closed);
+        let wire_edges: Vec<(Entity, usize, f32)> = network_wires
+            .iter()
+            .map(|(wire_entity, from, to)| {
+                let wire = wires.get(*wire_entity).ok();
+                let kind = wire.map(|wire| wire.kind).unwrap_or_default();
+                let length = match (positions.get(*from), positions.get(*to)) {
+                    (Ok(from_pos), Ok(to_pos)) => tile_distance(*from_pos, *to_pos),
+                    _ => 0.0,
+                };
+                let (from_kind, to_kind) = wire.map_or((PortKind::Any, PortKind::Any), |wire| {
+                    (
+                        port_kind(ports.get(wire.from).ok(), wire.from_port),
+                        port_kind(ports.get(wire.to).ok(), wire.to_port),
+                    )
+                });
+
+                let (from_node, to_node) = (nodes[from], nodes[to]);
+                let capacity = if open(from) || open(to) {
+                    0.0
                 } else {
-                    wire.from
+                    kind.throughput()
                 };
+                let edge = if from_kind == PortKind::Output || to_kind == PortKind::Input {
+                    graph.add_edge(from_node, to_node, capacity)
+                } else if from_kind == PortKind::Input || to_kind == PortKind::Output {
+                    graph.add_edge(to_node, from_node, capacity)
+                } else {
+                    graph.add_undirected_edge(from_node, to_node, capacity)
+                };
+                (*wire_entity, edge, (kind.loss_per_tile() * length).min(1.0))
+            })
+            .collect();
 
-                if !powered_entities.contains(&target) {
-                    powered_entities.insert(target);
-                    propagate_power_from(target, wires, connection_points, powered_entities);
+        let mut supply = 0.0;
+        let mut capacity = 0.0;
+        let mut source_edges = Vec::new();
+        for entity in &members {
+            if let Ok(power_source) = sources.get(*entity)
+                && power_source.powered
+            {
+                let output = power_source.output.max(0.0);
+                source_edges.push((*entity, graph.add_edge(source, nodes[entity], output)));
+                supply += output;
+                capacity += power_source.capacity.max(output);
+            }
+        }
+
+        let mut demand = 0.0;
+        let mut consumer_edges = Vec::new();
+        for entity in &members {
+            if let Ok(consumer) = consumers.get(*entity) {
+                // Tripped consumers stay off until their network is reset
+                let consumer_demand = if tripped.contains(*entity) {
+                    0.0
+                } else {
+                    consumer.demand.max(0.0)
+                };
+                let edge = graph.add_edge(nodes[entity], sink, consumer_demand);
+                consumer_edges.push((*entity, edge, consumer_demand));
+                demand += consumer_demand;
+            }
+        }
+
+        graph.max_flow(source, sink);
+        let delivered: f32 = consumer_edges
+            .iter()
+            .map(|(_, edge, _)| graph.flow(*edge))
+            .sum();
+
+        // Storage covers what generation couldn't deliver, or soaks up what's left over
+        let storage_edges = connect_storage(
+            &members,
+            &nodes,
+            &mut graph,
+            source,
+            sink,
+            delivered < demand,
+            dt,
+            &mut storage,
+        );
+        graph.max_flow(source, sink);
+
+        // Losses scale with what each wire carries and eat into everything delivered
+        let losses: f32 = wire_edges
+            .iter()
+            .map(|(_, edge, loss)| graph.flow(*edge).abs() * loss)
+            .sum();
+        let charged: f32 = storage_edges
+            .iter()
+            .filter(|(_, _, charging)| *charging)
+            .map(|(_, edge, _)| graph.flow(*edge))
+            .sum();
+        let carried = delivered + charged;
+        let delivery_factor = if carried > 0.0 {
+            (1.0 - losses / carried).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+
+        let mut storage_flow = 0.0;
+        for (entity, edge, charging) in &storage_edges {
+            if let Ok(mut unit) = storage.get_mut(*entity) {
+                let flow = if *charging {
+                    graph.flow(*edge) * delivery_factor
+                } else {
+                    -graph.flow(*edge)
+                };
+                unit.flow = flow;
+                unit.charge = (unit.charge + flow * dt).clamp(0.0, unit.capacity);
+                storage_flow += flow;
+            }
+        }
+
+        // Generators throttle and burn fuel by what's really drawn from them
+        for entity in &members {
+            let load = source_edges
+                .iter()
+                .find(|(source_entity, _)| source_entity == entity)
+                .map_or(0.0, |(_, edge)| graph.flow(*edge));
+            if let Ok(mut power_source) = sources.get_mut(*entity)
+                && power_source.load != load
+            {
+                power_source.load = load;
+            }
+        }
+
+        network.wire_flows = wire_edges
+            .iter()
+            .map(|(wire_entity, edge, _)| (*wire_entity, graph.flow(*edge).abs()))
+            .collect();
+
+        let reachable = graph.reachable(
+            members
+                .iter()
+                .filter(|entity| {
+                    sources.get(**entity).is_ok_and(|source| source.powered)
+                        || storage.contains(**entity)
+                })
+                .map(|entity| nodes[entity]),
+        );
+        let reachable_demand: f32 = consumer_edges
+            .iter()
+            .filter(|(entity, _, _)| reachable[nodes[entity]])
+            .map(|(_, _, consumer_demand)| consumer_demand)
+            .sum();
+
+        let available = supply - storage_flow.min(0.0);
+        let mut delivered_total = 0.0;
+        for (entity, edge, consumer_demand) in consumer_edges {
+            let received = graph.flow(edge) * delivery_factor;
+            delivered_total += received;
+
+            // Tripped consumers ask for nothing but get nothing either
+            let consumer_satisfaction = if tripped.contains(entity) {
+                0.0
+            } else if consumer_demand > 0.0 {
+                (received / consumer_demand).clamp(0.0, 1.0)
+            } else {
+                satisfaction(available, 0.0)
+            };
+
+            if let Ok(mut consumer) = consumers.get_mut(entity) {
+                // Only write on change so untouched consumers don't re-dirty the network
+                if consumer.satisfaction != consumer_satisfaction {
+                    consumer.satisfaction = consumer_satisfaction;
+                    consumer.powered = consumer_satisfaction > 0.0;
                 }
             }
         }
+
+        network.supply = supply;
+        network.demand = demand;
+        network.storage_flow = storage_flow;
+        network.losses = losses;
+        network.reachable_demand = reachable_demand;
+        network.capacity = capacity - storage_flow.min(0.0);
+        network.satisfaction = if demand > 0.0 {
+            (delivered_total / demand).clamp(0.0, 1.0)
+        } else {
+            satisfaction(available, demand)
+        };
     }
 }
+
+/// Adds every storage unit in the network to the flow graph, as a source while
+/// `discharging` or as a sink otherwise, limited by its rate and by what it can
+/// give or take within `dt`. Returns `(unit, edge, charging)` for each unit.
+#[allow(clippy::too_many_arguments)]
+fn connect_storage(
+    members: &[Entity],
+    nodes: &HashMap<Entity, usize>,
+    graph: &mut FlowGraph,
+    source: usize,
+    sink: usize,
+    discharging: bool,
+    dt: f32,
+    storage: &mut Query<&mut PowerStorage>,
+) -> Vec<(Entity, usize, bool)> {
+    let mut edges = Vec::new();
+
+    for entity in members {
+        let Ok(mut unit) = storage.get_mut(*entity) else {
+            continue;
+        };
+        unit.flow = 0.0;
+
+        if dt <= 0.0 {
+            continue;
+        }
+
+        if discharging {
+            let limit = unit.max_discharge_rate.min(unit.charge.max(0.0) / dt);
+            edges.push((*entity, graph.add_edge(source, nodes[entity], limit), false));
+        } else {
+            let limit = unit
+                .max_charge_rate
+                .min((unit.capacity - unit.charge).max(0.0) / dt);
+            edges.push((*entity, graph.add_edge(nodes[entity], sink, limit), true));
+        }
+    }
+
+    edges
+}
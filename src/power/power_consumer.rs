@@ -1,12 +1,30 @@
-use bevy::prelude::Component;
+use bevy::prelude::{Component, default};
 
 #[derive(Component)]
 pub struct PowerConsumer {
+    /// True while the consumer receives any power at all
     pub powered: bool,
+    /// Wattage required to run at full power
+    pub demand: f32,
+    /// Fraction of `demand` the network can currently deliver (0.0 - 1.0)
+    pub satisfaction: f32,
+}
+
+impl PowerConsumer {
+    pub fn new(demand: f32) -> Self {
+        Self {
+            demand,
+            ..default()
+        }
+    }
 }
 
 impl Default for PowerConsumer {
     fn default() -> Self {
-        Self { powered: false }
+        Self {
+            powered: false,
+            demand: 0.0,
+            satisfaction: 0.0,
+        }
     }
 }
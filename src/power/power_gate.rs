@@ -0,0 +1,14 @@
+use bevy::prelude::Component;
+
+/// Item that can break the circuit running through it, like a switch or relay.
+/// Wires attached to an open gate carry no power.
+#[derive(Component)]
+pub struct PowerGate {
+    pub closed: bool,
+}
+
+impl Default for PowerGate {
+    fn default() -> Self {
+        Self { closed: true }
+    }
+}
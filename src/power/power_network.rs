@@ -0,0 +1,346 @@
+use bevy::prelude::*;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Stable identifier of a connected component of the wire graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NetworkId(pub u32);
+
+#[derive(Default)]
+pub struct PowerNetwork {
+    pub members: HashSet<Entity>,
+    pub supply: f32,
+    pub demand: f32,
+    /// Wattage flowing into (positive) or out of (negative) storage
+    pub storage_flow: f32,
+    /// Wattage lost to wire resistance
+    pub losses: f32,
+    pub satisfaction: f32,
+    /// Demand of the consumers some source or storage can reach; those behind
+    /// an open gate or a wrong-way port can't be served at any supply
+    pub reachable_demand: f32,
+    /// Wattage the sources could give once ramped up, plus storage discharge
+    pub capacity: f32,
+    /// Wattage carried by each wire in the network
+    pub wire_flows: HashMap<Entity, f32>,
+}
+
+/// Persistent view of the wire graph, kept up to date incrementally as
+/// connection points and wires come and go instead of being rebuilt each frame.
+#[derive(Resource, Default)]
+pub struct PowerNetworks {
+    next_id: u32,
+    networks: HashMap<NetworkId, PowerNetwork>,
+    membership: HashMap<Entity, NetworkId>,
+    // node -> (wire -> neighbour)
+    adjacency: HashMap<Entity, HashMap<Entity, Entity>>,
+    // wire -> (from, to)
+    wires: HashMap<Entity, (Entity, Entity)>,
+    dirty: HashSet<NetworkId>,
+}
+
+impl PowerNetworks {
+    pub fn network_of(&self, entity: Entity) -> Option<NetworkId> {
+        self.membership.get(&entity).copied()
+    }
+
+    pub fn get(&self, id: NetworkId) -> Option<&PowerNetwork> {
+        self.networks.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: NetworkId) -> Option<&mut PowerNetwork> {
+        self.networks.get_mut(&id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&NetworkId, &PowerNetwork)> {
+        self.networks.iter()
+    }
+
+    /// Every wire inside a network as `(wire, from, to)`
+    pub fn wires_in(&self, id: NetworkId) -> Vec<(Entity, Entity, Entity)> {
+        let Some(network) = self.networks.get(&id) else {
+            return Vec::new();
+        };
+
+        let mut wires: Vec<(Entity, Entity, Entity)> = network
+            .members
+            .iter()
+            .filter_map(|member| self.adjacency.get(member))
+            .flat_map(|edges| edges.keys())
+            .filter_map(|wire| self.wires.get(wire).map(|(from, to)| (*wire, *from, *to)))
+            .collect();
+        // Each wire is seen from both ends
+        wires.sort();
+        wires.dedup();
+        wires
+    }
+
+    pub fn mark_dirty(&mut self, entity: Entity) {
+        if let Some(id) = self.network_of(entity) {
+            self.dirty.insert(id);
+        }
+    }
+
+    /// Drains the networks whose topology or members changed since the last call
+    pub fn take_dirty(&mut self) -> Vec<NetworkId> {
+        let mut dirty: Vec<NetworkId> = self
+            .dirty
+            .drain()
+            .filter(|id| self.networks.contains_key(id))
+            .collect();
+        dirty.sort();
+        dirty
+    }
+
+    pub fn add_node(&mut self, entity: Entity) {
+        if self.membership.contains_key(&entity) {
+            return;
+        }
+
+        let id = self.allocate_id();
+        let mut network = PowerNetwork::default();
+        network.members.insert(entity);
+
+        self.networks.insert(id, network);
+        self.membership.insert(entity, id);
+        self.adjacency.entry(entity).or_default();
+        self.dirty.insert(id);
+    }
+
+    pub fn remove_node(&mut self, entity: Entity) {
+        // Detach every wire first so the remaining graph gets split correctly
+        let attached: Vec<Entity> = self
+            .adjacency
+            .get(&entity)
+            .map(|edges| edges.keys().copied().collect())
+            .unwrap_or_default();
+        for wire in attached {
+            self.remove_edge(wire);
+        }
+
+        self.adjacency.remove(&entity);
+        if let Some(id) = self.membership.remove(&entity)
+            && let Some(network) = self.networks.get_mut(&id)
+        {
+            network.members.remove(&entity);
+            if network.members.is_empty() {
+                self.networks.remove(&id);
+            }
+        }
+    }
+
+    pub fn add_edge(&mut self, wire: Entity, from: Entity, to: Entity) {
+        if self.wires.contains_key(&wire) {
+            return;
+        }
+
+        self.add_node(from);
+        self.add_node(to);
+
+        self.wires.insert(wire, (from, to));
+        self.adjacency.entry(from).or_default().insert(wire, to);
+        self.adjacency.entry(to).or_default().insert(wire, from);
+
+        let (Some(a), Some(b)) = (self.network_of(from), self.network_of(to)) else {
+            return;
+        };
+
+        if a == b {
+            self.dirty.insert(a);
+            return;
+        }
+
+        // Union by size: fold the smaller network into the larger one so the
+        // bigger network keeps its id and we move as few members as possible
+        let a_len = self.networks.get(&a).map_or(0, |n| n.members.len());
+        let b_len = self.networks.get(&b).map_or(0, |n| n.members.len());
+        let (keep, absorb) = if a_len >= b_len { (a, b) } else { (b, a) };
+
+        if let Some(absorbed) = self.networks.remove(&absorb) {
+            for member in &absorbed.members {
+                self.membership.insert(*member, keep);
+            }
+            if let Some(network) = self.networks.get_mut(&keep) {
+                network.members.extend(absorbed.members);
+            }
+        }
+
+        self.dirty.remove(&absorb);
+        self.dirty.insert(keep);
+    }
+
+    pub fn remove_edge(&mut self, wire: Entity) {
+        let Some((from, to)) = self.wires.remove(&wire) else {
+            return;
+        };
+
+        if let Some(edges) = self.adjacency.get_mut(&from) {
+            edges.remove(&wire);
+        }
+        if let Some(edges) = self.adjacency.get_mut(&to) {
+            edges.remove(&wire);
+        }
+
+        let Some(id) = self.network_of(from) else {
+            return;
+        };
+        self.dirty.insert(id);
+
+        // Split detection: if `to` is still reachable the network is intact
+        let reached = self.reachable_from(from);
+        if reached.contains(&to) {
+            return;
+        }
+
+        let total = self.networks.get(&id).map_or(0, |n| n.members.len());
+        // Move whichever half is smaller into a fresh network
+        let split_off = if reached.len() * 2 <= total {
+            reached
+        } else {
+            self.reachable_from(to)
+        };
+
+        let new_id = self.allocate_id();
+        if let Some(network) = self.networks.get_mut(&id) {
+            network.members.retain(|member| !split_off.contains(member));
+        }
+        for member in &split_off {
+            self.membership.insert(*member, new_id);
+        }
+        self.networks.insert(
+            new_id,
+            PowerNetwork {
+                members: split_off,
+                ..default()
+            },
+        );
+        self.dirty.insert(new_id);
+    }
+
+    fn reachable_from(&self, start: Entity) -> HashSet<Entity> {
+        // Iterative traversal so long pole chains can't overflow the stack
+        let mut visited = HashSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+
+        while let Some(node) = queue.pop_front() {
+            let Some(edges) = self.adjacency.get(&node) else {
+                continue;
+            };
+            for neighbour in edges.values() {
+                if visited.insert(*neighbour) {
+                    queue.push_back(*neighbour);
+                }
+            }
+        }
+
+        visited
+    }
+
+    fn allocate_id(&mut self) -> NetworkId {
+        let id = NetworkId(self.next_id);
+        self.next_id += 1;
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entities<const N: usize>() -> [Entity; N] {
+        std::array::from_fn(|index| Entity::from_raw(index as u32 + 1))
+    }
+
+    fn members(networks: &PowerNetworks, entity: Entity) -> HashSet<Entity> {
+        networks
+            .network_of(entity)
+            .and_then(|id| networks.get(id))
+            .map(|network| network.members.clone())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn wiring_two_nodes_merges_their_networks() {
+        let [a, b, wire] = entities();
+        let mut networks = PowerNetworks::default();
+        networks.add_node(a);
+        networks.add_node(b);
+        assert_ne!(networks.network_of(a), networks.network_of(b));
+
+        networks.add_edge(wire, a, b);
+        assert_eq!(networks.network_of(a), networks.network_of(b));
+        assert_eq!(networks.iter().count(), 1);
+        assert_eq!(members(&networks, a), HashSet::from([a, b]));
+    }
+
+    #[test]
+    fn merging_keeps_the_larger_networks_id() {
+        let [a, b, c, ab, bc] = entities();
+        let mut networks = PowerNetworks::default();
+        networks.add_edge(ab, a, b);
+        let larger = networks.network_of(a);
+        networks.add_node(c);
+
+        networks.add_edge(bc, b, c);
+        assert_eq!(networks.network_of(c), larger);
+        assert_eq!(
+            networks.take_dirty(),
+            larger.into_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn cutting_a_bridge_splits_the_network() {
+        let [a, b, c, d, ab, bc, cd] = entities();
+        let mut networks = PowerNetworks::default();
+        networks.add_edge(ab, a, b);
+        networks.add_edge(bc, b, c);
+        networks.add_edge(cd, c, d);
+        let original = networks.network_of(a);
+        networks.take_dirty();
+
+        networks.remove_edge(ab);
+        // The smaller half moves out, the rest keeps the id
+        assert_eq!(networks.network_of(b), original);
+        assert_ne!(networks.network_of(a), original);
+        assert_eq!(members(&networks, a), HashSet::from([a]));
+        assert_eq!(members(&networks, b), HashSet::from([b, c, d]));
+        assert_eq!(networks.take_dirty().len(), 2);
+        assert!(
+            networks
+                .wires_in(networks.network_of(a).unwrap())
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn cutting_a_loop_keeps_the_network_whole() {
+        let [a, b, c, ab, bc, ca] = entities();
+        let mut networks = PowerNetworks::default();
+        networks.add_edge(ab, a, b);
+        networks.add_edge(bc, b, c);
+        networks.add_edge(ca, c, a);
+
+        networks.remove_edge(ab);
+        assert_eq!(networks.iter().count(), 1);
+        assert_eq!(members(&networks, a), HashSet::from([a, b, c]));
+        assert_eq!(
+            networks.wires_in(networks.network_of(a).unwrap()),
+            vec![(bc, b, c), (ca, c, a)]
+        );
+    }
+
+    #[test]
+    fn removing_a_node_drops_its_wires_and_empty_network() {
+        let [a, b, c, ab, bc] = entities();
+        let mut networks = PowerNetworks::default();
+        networks.add_edge(ab, a, b);
+        networks.add_edge(bc, b, c);
+
+        networks.remove_node(b);
+        assert_eq!(networks.network_of(b), None);
+        assert_ne!(networks.network_of(a), networks.network_of(c));
+
+        networks.remove_node(a);
+        assert_eq!(networks.iter().count(), 1);
+    }
+}
@@ -3,10 +3,22 @@ use bevy::prelude::Component;
 #[derive(Component)]
 pub struct PowerSource {
     pub powered: bool,
+    /// Wattage currently offered to the connected network
+    pub output: f32,
+    /// Wattage the network actually draws from it, written by the solver
+    pub load: f32,
+    /// Wattage it can ramp up to if `output` is still climbing; sources that
+    /// can't ramp leave it at 0 and count with their `output`
+    pub capacity: f32,
 }
 
 impl Default for PowerSource {
     fn default() -> Self {
-        Self { powered: true }
+        Self {
+            powered: true,
+            output: 0.0,
+            load: 0.0,
+            capacity: 0.0,
+        }
     }
 }
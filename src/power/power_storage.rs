@@ -0,0 +1,45 @@
+use bevy::prelude::{Component, default};
+
+#[derive(Component)]
+pub struct PowerStorage {
+    /// Energy the storage can hold, in watt-seconds
+    pub capacity: f32,
+    /// Energy currently held, in watt-seconds
+    pub charge: f32,
+    pub max_charge_rate: f32,
+    pub max_discharge_rate: f32,
+    /// Wattage flowing in (positive) or out (negative) during the last update
+    pub flow: f32,
+}
+
+impl PowerStorage {
+    pub fn new(capacity: f32, max_charge_rate: f32, max_discharge_rate: f32) -> Self {
+        Self {
+            capacity,
+            max_charge_rate,
+            max_discharge_rate,
+            ..default()
+        }
+    }
+
+    /// Charge level as a fraction of capacity (0.0 - 1.0)
+    pub fn level(&self) -> f32 {
+        if self.capacity <= 0.0 {
+            0.0
+        } else {
+            (self.charge / self.capacity).clamp(0.0, 1.0)
+        }
+    }
+}
+
+impl Default for PowerStorage {
+    fn default() -> Self {
+        Self {
+            capacity: 0.0,
+            charge: 0.0,
+            max_charge_rate: 0.0,
+            max_discharge_rate: 0.0,
+            flow: 0.0,
+        }
+    }
+}
@@ -0,0 +1,519 @@
+use crate::items::definition::ItemId;
+use crate::items::generator::{FuelType, Generator};
+use crate::items::light::Light;
+use crate::items::registry::Item;
+use crate::items::relay::{Relay, RelayCondition};
+use crate::power::overload::Tripped;
+use crate::power::power_gate::PowerGate;
+use crate::power::power_storage::PowerStorage;
+use crate::wire_system::port::Facing;
+use crate::wire_system::{Wire, WireKind, merge_duplicate_wires};
+use crate::world::clock::WorldClock;
+use crate::world::edit;
+use crate::world::grid::GridPosition;
+use crate::world::history::EditHistory;
+use bevy::asset::io::file::FileAssetReader;
+use bevy::input::common_conditions::input_just_pressed;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+mod v1;
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                quick_save.run_if(input_just_pressed(KeyCode::F5)),
+                quick_load.run_if(input_just_pressed(KeyCode::F9)),
+            ),
+        );
+    }
+}
+
+/// Bumped whenever the save layout changes; older files are migrated on load
+pub const SAVE_VERSION: u32 = 2;
+
+const SAVES_DIR: &str = "saves";
+const QUICK_SAVE_FILE: &str = "quicksave.ron";
+
+/// Read first so the rest of the file can be parsed with the right layout
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SaveFile {
+    pub version: u32,
+    pub camera: CameraState,
+    pub clock: Option<WorldClock>,
+    pub items: Vec<ItemSnapshot>,
+    /// Wires are stored by endpoint position since entity ids don't survive a reload
+    pub wires: Vec<WireSnapshot>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct CameraState {
+    pub translation: [f32; 3],
+    pub scale: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ItemSnapshot {
+    pub id: ItemId,
+    pub pos: GridPosition,
+    pub facing: Facing,
+    pub generator: Option<GeneratorState>,
+    pub storage: Option<StorageState>,
+    pub light: Option<LightState>,
+    pub gate: Option<GateState>,
+    /// Consumer cut off by an overload trip
+    pub tripped: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GeneratorState {
+    /// `None` in saves from before fuel types; the item's own fuel is kept
+    pub fuel_type: Option<FuelType>,
+    pub fuel_amount: f32,
+    pub output: f32,
+    pub is_active: bool,
+    /// Progress of the current burn cycle, in seconds
+    pub burn_elapsed: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct StorageState {
+    pub charge: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LightState {
+    pub switched_on: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GateState {
+    pub closed: bool,
+    /// Only relays have a condition
+    pub condition: Option<RelayCondition>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct WireSnapshot {
+    pub from: GridPosition,
+    pub to: GridPosition,
+    pub kind: WireKind,
+    /// Ports the wire attaches to; `None` on items without ports and in saves
+    /// from before ports, where they're re-picked
+    pub from_port: Option<u8>,
+    pub to_port: Option<u8>,
+}
+
+impl ItemSnapshot {
+    /// A freshly placed item with default component state
+    pub fn new(id: ItemId, pos: GridPosition, facing: Facing) -> Self {
+        Self {
+            id,
+            pos,
+            facing,
+            generator: None,
+            storage: None,
+            light: None,
+            gate: None,
+            tripped: false,
+        }
+    }
+
+    /// The same item `by` tiles away
+    pub fn offset(&self, by: IVec2) -> Self {
+        Self {
+            pos: self.pos.offset(by),
+            ..self.clone()
+        }
+    }
+
+    pub fn capture(world: &World, entity: Entity) -> Option<Self> {
+        let item = world.get::<Item>(entity)?;
+        let pos = world.get::<GridPosition>(entity)?;
+
+        Some(Self {
+            id: item.0.clone(),
+            pos: *pos,
+            facing: world.get::<Facing>(entity).copied().unwrap_or_default(),
+            generator: world
+                .get::<Generator>(entity)
+                .map(|generator| GeneratorState {
+                    fuel_type: Some(generator.fuel_type),
+                    fuel_amount: generator.fuel_amount,
+                    output: generator.output,
+                    is_active: generator.is_active,
+                    burn_elapsed: generator.burn_timer.elapsed_secs(),
+                }),
+            storage: world
+                .get::<PowerStorage>(entity)
+                .map(|storage| StorageState {
+                    charge: storage.charge,
+                }),
+            light: world.get::<Light>(entity).map(|light| LightState {
+                switched_on: light.switched_on,
+            }),
+            gate: world.get::<PowerGate>(entity).map(|gate| GateState {
+                closed: gate.closed,
+                condition: world.get::<Relay>(entity).map(|relay| relay.condition),
+            }),
+            tripped: world.get::<Tripped>(entity).is_some(),
+        })
+    }
+
+    /// Places the item and restores its component state
+    pub fn restore(&self, world: &mut World) -> Option<Entity> {
+        let entity = edit::place_item(world, &self.id, self.pos, self.facing)?;
+
+        if let (Some(state), Some(mut generator)) =
+            (&self.generator, world.get_mut::<Generator>(entity))
+        {
+            if let Some(fuel_type) = state.fuel_type {
+                generator.set_fuel_type(fuel_type);
+            }
+            generator.fuel_amount = state.fuel_amount.clamp(0.0, generator.tank_capacity);
+            generator.output = state.output;
+            generator.is_active = state.is_active;
+            generator
+                .burn_timer
+                .set_elapsed(Duration::from_secs_f32(state.burn_elapsed.max(0.0)));
+        }
+
+        if let (Some(state), Some(mut storage)) =
+            (&self.storage, world.get_mut::<PowerStorage>(entity))
+        {
+            storage.charge = state.charge.clamp(0.0, storage.capacity);
+        }
+
+        if let (Some(state), Some(mut light)) = (&self.light, world.get_mut::<Light>(entity)) {
+            light.switched_on = state.switched_on;
+        }
+
+        if let Some(state) = &self.gate {
+            if let Some(mut gate) = world.get_mut::<PowerGate>(entity) {
+                gate.closed = state.closed;
+            }
+            if let (Some(condition), Some(mut relay)) =
+                (state.condition, world.get_mut::<Relay>(entity))
+            {
+                relay.condition = condition;
+            }
+        }
+
+        if self.tripped {
+            world.entity_mut(entity).insert(Tripped);
+        }
+
+        Some(entity)
+    }
+}
+
+impl WireSnapshot {
+    pub fn offset(&self, by: IVec2) -> Self {
+        Self {
+            from: self.from.offset(by),
+            to: self.to.offset(by),
+            ..*self
+        }
+    }
+
+    pub fn capture(world: &World, wire: &Wire) -> Option<Self> {
+        Some(Self {
+            from: *world.get::<GridPosition>(wire.from)?,
+            to: *world.get::<GridPosition>(wire.to)?,
+            kind: wire.kind,
+            from_port: wire.from_port,
+            to_port: wire.to_port,
+        })
+    }
+
+    pub fn restore(&self, world: &mut World) -> Option<Entity> {
+        let from = edit::item_at(world, self.from)?;
+        let to = edit::item_at(world, self.to)?;
+        edit::connect_at(
+            world,
+            from,
+            to,
+            self.kind,
+            Some((self.from_port, self.to_port)),
+        )
+    }
+}
+
+impl SaveFile {
+    pub fn capture(world: &mut World) -> Self {
+        let item_entities: Vec<Entity> = world
+            .query_filtered::<Entity, With<Item>>()
+            .iter(world)
+            .collect();
+        let mut items: Vec<ItemSnapshot> = item_entities
+            .into_iter()
+            .filter_map(|entity| ItemSnapshot::capture(world, entity))
+            .collect();
+        // Keep files stable between saves of the same world
+        items.sort_by_key(|item| (item.pos.x, item.pos.y));
+
+        let wires: Vec<WireSnapshot> = world
+            .query::<&Wire>()
+            .iter(world)
+            .filter_map(|wire| WireSnapshot::capture(world, wire))
+            .collect();
+
+        let camera = world
+            .query_filtered::<(&Transform, &Projection), With<Camera2d>>()
+            .iter(world)
+            .next()
+            .map(|(transform, projection)| CameraState {
+                translation: transform.translation.to_array(),
+                scale: match projection {
+                    Projection::Orthographic(orthographic) => orthographic.scale,
+                    _ => 1.0,
+                },
+            })
+            .unwrap_or_default();
+
+        Self {
+            version: SAVE_VERSION,
+            camera,
+            clock: Some(world.resource::<WorldClock>().clone()),
+            items,
+            wires,
+        }
+    }
+
+    /// Replaces everything placed in the world with the contents of this save
+    pub fn restore(&self, world: &mut World) {
+        edit::clear_all(world);
+        // Edits are recorded by position and would replay against the wrong layout
+        if let Some(mut history) = world.get_resource_mut::<EditHistory>() {
+            history.clear();
+        }
+
+        let placed = self
+            .items
+            .iter()
+            .filter(|item| item.restore(world).is_some())
+            .count();
+        let wired = self
+            .wires
+            .iter()
+            .filter(|wire| wire.restore(world).is_some())
+            .count();
+        merge_duplicate_wires(world);
+        if let Some(clock) = &self.clock {
+            world.insert_resource(clock.clone());
+        }
+
+        if placed < self.items.len() || wired < self.wires.len() {
+            println!(
+                "Restored {}/{} items and {}/{} wires",
+                placed,
+                self.items.len(),
+                wired,
+                self.wires.len()
+            );
+        }
+
+        let mut cameras =
+            world.query_filtered::<(&mut Transform, &mut Projection), With<Camera2d>>();
+        for (mut transform, mut projection) in cameras.iter_mut(world) {
+            transform.translation = Vec3::from_array(self.camera.translation);
+            if let Projection::Orthographic(orthographic) = &mut *projection {
+                orthographic.scale = self.camera.scale;
+            }
+        }
+    }
+
+    pub fn to_ron(&self) -> Result<String, String> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn from_ron(text: &str) -> Result<Self, String> {
+        let header: SaveHeader = ron::from_str(text).map_err(|err| err.to_string())?;
+        migrate(header.version, text)
+    }
+}
+
+/// Parses a save written with `version`, upgrading it to the current layout
+fn migrate(version: u32, text: &str) -> Result<SaveFile, String> {
+    match version {
+        SAVE_VERSION => ron::from_str(text).map_err(|err| err.to_string()),
+        // Older layouts are parsed as written and converted upward
+        1 => ron::from_str::<v1::SaveFile>(text)
+            .map(SaveFile::from)
+            .map_err(|err| err.to_string()),
+        newer if newer > SAVE_VERSION => Err(format!(
+            "save version {} is newer than supported version {}",
+            newer, SAVE_VERSION
+        )),
+        unknown => Err(format!("unknown save version {}", unknown)),
+    }
+}
+
+impl From<v1::SaveFile> for SaveFile {
+    /// Items face north and wires are copper, as everything was before those
+    /// could change; ports are re-picked on load
+    fn from(save: v1::SaveFile) -> Self {
+        let items = save
+            .items
+            .into_iter()
+            .map(|item| ItemSnapshot {
+                generator: item.generator.map(|state| GeneratorState {
+                    fuel_type: None,
+                    fuel_amount: state.fuel_amount,
+                    output: state.output,
+                    is_active: state.is_active,
+                    burn_elapsed: state.burn_elapsed,
+                }),
+                storage: item.storage,
+                ..ItemSnapshot::new(item.id, item.pos, Facing::North)
+            })
+            .collect();
+        let wires = save
+            .wires
+            .into_iter()
+            .map(|wire| WireSnapshot {
+                from: wire.from,
+                to: wire.to,
+                kind: WireKind::Copper,
+                from_port: None,
+                to_port: None,
+            })
+            .collect();
+
+        Self {
+            version: SAVE_VERSION,
+            camera: save.camera,
+            clock: None,
+            items,
+            wires,
+        }
+    }
+}
+
+fn quick_save_path() -> PathBuf {
+    FileAssetReader::get_base_path()
+        .join(SAVES_DIR)
+        .join(QUICK_SAVE_FILE)
+}
+
+fn quick_save(world: &mut World) {
+    let path = quick_save_path();
+    let result = SaveFile::capture(world).to_ron().and_then(|text| {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+        fs::write(&path, text).map_err(|err| err.to_string())
+    });
+
+    match result {
+        Ok(()) => println!("Saved to {:?}", path),
+        Err(err) => println!("Save failed: {}", err),
+    }
+}
+
+fn quick_load(world: &mut World) {
+    let path = quick_save_path();
+    let save = fs::read_to_string(&path)
+        .map_err(|err| err.to_string())
+        .and_then(|text| SaveFile::from_ron(&text));
+
+    match save {
+        Ok(save) => {
+            save.restore(world);
+            println!("Loaded {:?}", path);
+        }
+        Err(err) => println!("Load failed: {}", err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A version 1 save from before clocks, facings, gates and wire ports
+    const OLD_SAVE: &str = r#"(
+        version: 1,
+        camera: (translation: (0.0, 0.0, 0.0), scale: 1.0),
+        items: [
+            (id: "power_pole", pos: (x: 0, y: 0)),
+            (id: "light", pos: (x: 3, y: 0)),
+            (
+                id: "generator",
+                pos: (x: 0, y: 3),
+                generator: Some((fuel_amount: 40.0, output: 12.0, is_active: true, burn_elapsed: 0.5)),
+            ),
+            (id: "battery", pos: (x: 3, y: 3), storage: Some((charge: 250.0))),
+        ],
+        wires: [
+            (from: (x: 0, y: 0), to: (x: 3, y: 0)),
+        ],
+    )"#;
+
+    #[test]
+    fn version_1_saves_migrate() {
+        let save = SaveFile::from_ron(OLD_SAVE).unwrap();
+        assert_eq!(save.version, SAVE_VERSION);
+        assert!(save.clock.is_none());
+        assert_eq!(save.items.len(), 4);
+        assert_eq!(save.items[1].facing, Facing::North);
+        assert!(save.items[1].gate.is_none() && !save.items[1].tripped);
+
+        let generator = save.items[2].generator.as_ref().unwrap();
+        assert_eq!(generator.fuel_type, None);
+        assert_eq!(generator.fuel_amount, 40.0);
+        assert!(generator.is_active);
+        assert_eq!(save.items[3].storage.as_ref().unwrap().charge, 250.0);
+
+        let wire = &save.wires[0];
+        assert_eq!(wire.kind, WireKind::Copper);
+        assert_eq!((wire.from_port, wire.to_port), (None, None));
+    }
+
+    #[test]
+    fn saves_round_trip() {
+        let mut save = SaveFile::from_ron(OLD_SAVE).unwrap();
+        save.wires[0].kind = WireKind::HighVoltage;
+        save.wires[0].to_port = Some(2);
+
+        let loaded = SaveFile::from_ron(&save.to_ron().unwrap()).unwrap();
+        assert_eq!(loaded.version, SAVE_VERSION);
+        assert_eq!(loaded.wires[0].kind, WireKind::HighVoltage);
+        assert_eq!(loaded.wires[0].to_port, Some(2));
+        assert_eq!(loaded.items[1].pos, GridPosition { x: 3, y: 0 });
+    }
+
+    fn load_error(text: &str) -> String {
+        SaveFile::from_ron(text).err().unwrap_or_default()
+    }
+
+    #[test]
+    fn current_saves_need_every_field() {
+        let save = SaveFile::from_ron(OLD_SAVE).unwrap().to_ron().unwrap();
+        assert!(SaveFile::from_ron(&save).is_ok());
+
+        let missing_facing = save.replacen("facing: North,", "", 1);
+        assert!(load_error(&missing_facing).contains("facing"));
+    }
+
+    #[test]
+    fn newer_and_unknown_versions_are_rejected() {
+        let newer = OLD_SAVE.replace("version: 1", &format!("version: {}", SAVE_VERSION + 1));
+        assert!(load_error(&newer).contains("newer"));
+
+        let unknown = OLD_SAVE.replace("version: 1", "version: 0");
+        assert!(load_error(&unknown).contains("unknown"));
+    }
+}
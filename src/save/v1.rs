@@ -0,0 +1,37 @@
+use crate::items::definition::ItemId;
+use crate::save::{CameraState, StorageState};
+use crate::world::grid::GridPosition;
+use serde::Deserialize;
+
+/// Layout of version 1 saves, from before facings, wire kinds and ports, fuel
+/// types, lights, gates, trips and the day/night clock
+#[derive(Deserialize)]
+pub struct SaveFile {
+    pub camera: CameraState,
+    pub items: Vec<ItemSnapshot>,
+    pub wires: Vec<WireSnapshot>,
+}
+
+#[derive(Deserialize)]
+pub struct ItemSnapshot {
+    pub id: ItemId,
+    pub pos: GridPosition,
+    #[serde(default)]
+    pub generator: Option<GeneratorState>,
+    #[serde(default)]
+    pub storage: Option<StorageState>,
+}
+
+#[derive(Deserialize)]
+pub struct GeneratorState {
+    pub fuel_amount: f32,
+    pub output: f32,
+    pub is_active: bool,
+    pub burn_elapsed: f32,
+}
+
+#[derive(Deserialize)]
+pub struct WireSnapshot {
+    pub from: GridPosition,
+    pub to: GridPosition,
+}
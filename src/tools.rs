@@ -1 +1,6 @@
+pub mod blueprint;
 pub mod build_tool;
+pub mod drag_place;
+pub mod fuel_tool;
+pub mod ghost;
+pub mod select_tool;
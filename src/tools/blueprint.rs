@@ -0,0 +1,321 @@
+use crate::items::definition::ItemId;
+use crate::items::registry::ItemRegistry;
+use crate::save::{ItemSnapshot, WireSnapshot};
+use crate::tools::build_tool::{BuildTool, update_tool_text};
+use crate::tools::ghost::{GhostStyle, spawn_ghost_wires};
+use crate::tools::select_tool::Selection;
+use crate::ui::MainText;
+use crate::wire_system::Wire;
+use crate::world::grid::{Footprint, GridCursor, GridPosition};
+use crate::world::history;
+use crate::world::tile_index::TileIndex;
+use bevy::asset::io::file::FileAssetReader;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+pub struct BlueprintPlugin;
+
+impl Plugin for BlueprintPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BlueprintLibrary>()
+            .add_systems(Startup, load_blueprints)
+            .add_systems(
+                Update,
+                (
+                    save_blueprint_input,
+                    blueprint_input,
+                    update_blueprint_ghosts,
+                )
+                    .chain(),
+            );
+    }
+}
+
+const BLUEPRINTS_DIR: &str = "blueprints";
+/// Switches to stamping blueprints from the library
+const BLUEPRINT_HOTKEY: KeyCode = KeyCode::KeyP;
+/// Saves the select tool's selection as a new blueprint
+const SAVE_BLUEPRINT_KEY: KeyCode = KeyCode::KeyB;
+/// Cycles through the library while the blueprint tool is active
+const NEXT_BLUEPRINT_KEY: KeyCode = KeyCode::KeyQ;
+
+/// A group of items and the wires between them, with positions relative to
+/// the lower-left corner of the group. Items are stored without their state,
+/// so stamped generators start empty.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Blueprint {
+    pub name: String,
+    pub items: Vec<ItemSnapshot>,
+    pub wires: Vec<WireSnapshot>,
+}
+
+impl Blueprint {
+    pub fn capture(world: &mut World, name: String, items: &[Entity]) -> Option<Self> {
+        let snapshots: Vec<ItemSnapshot> = items
+            .iter()
+            .filter_map(|item| ItemSnapshot::capture(world, *item))
+            .map(|snapshot| ItemSnapshot::new(snapshot.id, snapshot.pos, snapshot.facing))
+            .collect();
+        let origin = snapshots
+            .iter()
+            .map(|item| IVec2::new(item.pos.x, item.pos.y))
+            .reduce(IVec2::min)?;
+
+        let wires: Vec<WireSnapshot> = world
+            .query::<&Wire>()
+            .iter(world)
+            .filter(|wire| items.contains(&wire.from) && items.contains(&wire.to))
+            .filter_map(|wire| WireSnapshot::capture(world, wire))
+            .map(|wire| wire.offset(-origin))
+            .collect();
+
+        Some(Self {
+            name,
+            items: snapshots.iter().map(|item| item.offset(-origin)).collect(),
+            wires,
+        })
+    }
+}
+
+/// Blueprints saved under `blueprints/` beside `saves/`, one `.ron` file each
+#[derive(Resource, Default)]
+pub struct BlueprintLibrary {
+    blueprints: Vec<Blueprint>,
+    selected: usize,
+}
+
+impl BlueprintLibrary {
+    pub fn selected(&self) -> Option<&Blueprint> {
+        self.blueprints.get(self.selected)
+    }
+}
+
+fn blueprints_dir() -> PathBuf {
+    FileAssetReader::get_base_path().join(BLUEPRINTS_DIR)
+}
+
+fn load_blueprints(mut library: ResMut<BlueprintLibrary>) {
+    let Ok(entries) = fs::read_dir(blueprints_dir()) else {
+        return;
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "ron"))
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let blueprint = fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| ron::from_str::<Blueprint>(&text).map_err(|err| err.to_string()));
+        match blueprint {
+            Ok(blueprint) => library.blueprints.push(blueprint),
+            Err(err) => println!("Failed to load blueprint {:?}: {}", path, err),
+        }
+    }
+}
+
+/// Writes `blueprint` to its own file in the library directory
+fn write_blueprint(blueprint: &Blueprint) -> Result<PathBuf, String> {
+    let dir = blueprints_dir();
+    fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+    let path = dir.join(format!("{}.ron", blueprint.name));
+    let text = ron::ser::to_string_pretty(blueprint, ron::ser::PrettyConfig::default())
+        .map_err(|err| err.to_string())?;
+    fs::write(&path, text).map_err(|err| err.to_string())?;
+    Ok(path)
+}
+
+fn save_blueprint_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    tool: Res<BuildTool>,
+    selection: Res<Selection>,
+    mut commands: Commands,
+) {
+    if *tool != BuildTool::Select
+        || !keys.just_pressed(SAVE_BLUEPRINT_KEY)
+        || selection.items.is_empty()
+    {
+        return;
+    }
+
+    let items = selection.items.clone();
+    commands.queue(move |world: &mut World| {
+        // First name not already taken on disk
+        let name = (1..)
+            .map(|index| format!("blueprint_{}", index))
+            .find(|name| !blueprints_dir().join(format!("{}.ron", name)).exists())
+            .unwrap_or_default();
+        let Some(blueprint) = Blueprint::capture(world, name, &items) else {
+            return;
+        };
+
+        match write_blueprint(&blueprint) {
+            Ok(path) => println!(
+                "Saved blueprint {} with {} items and {} wires to {:?}",
+                blueprint.name,
+                blueprint.items.len(),
+                blueprint.wires.len(),
+                path
+            ),
+            Err(err) => println!("Saving blueprint failed: {}", err),
+        }
+        world
+            .resource_mut::<BlueprintLibrary>()
+            .blueprints
+            .push(blueprint);
+    });
+}
+
+pub fn blueprint_tool_label(library: &BlueprintLibrary) -> String {
+    match library.selected() {
+        Some(blueprint) => format!("Blueprint ({})", blueprint.name),
+        None => "Blueprint (library empty)".into(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn blueprint_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    cursor: GridCursor,
+    mut tool: ResMut<BuildTool>,
+    mut library: ResMut<BlueprintLibrary>,
+    mut text_spans: Query<&mut TextSpan>,
+    main_text_entities: Query<&Children, With<MainText>>,
+    mut commands: Commands,
+) {
+    if keys.just_pressed(BLUEPRINT_HOTKEY) {
+        *tool = BuildTool::Blueprint;
+        update_tool_text(
+            &blueprint_tool_label(&library),
+            &mut text_spans,
+            &main_text_entities,
+        );
+    }
+    if *tool != BuildTool::Blueprint {
+        return;
+    }
+
+    if keys.just_pressed(NEXT_BLUEPRINT_KEY) && !library.blueprints.is_empty() {
+        library.selected = (library.selected + 1) % library.blueprints.len();
+        update_tool_text(
+            &blueprint_tool_label(&library),
+            &mut text_spans,
+            &main_text_entities,
+        );
+    }
+
+    if !buttons.just_pressed(MouseButton::Right) {
+        return;
+    }
+    let (Some(blueprint), Some(pos)) = (library.selected(), cursor.grid_position()) else {
+        return;
+    };
+    let blueprint = blueprint.clone();
+    let offset = IVec2::new(pos.x, pos.y);
+    commands.queue(move |world: &mut World| {
+        history::place_group_recorded(world, &blueprint.items, &blueprint.wires, offset);
+    });
+}
+
+/// What the blueprint ghosts show, so they're only rebuilt on change
+#[derive(Default, PartialEq)]
+struct BlueprintView {
+    /// Item, position, rotation and whether it's blocked there
+    items: Vec<(ItemId, Vec2, Quat, bool)>,
+    wires: Vec<(Vec2, Vec2)>,
+}
+
+/// Translucent preview of the blueprint under the cursor
+#[derive(Component, Clone)]
+struct BlueprintGhost;
+
+#[allow(clippy::too_many_arguments)]
+fn update_blueprint_ghosts(
+    cursor: GridCursor,
+    tool: Res<BuildTool>,
+    library: Res<BlueprintLibrary>,
+    registry: Res<ItemRegistry>,
+    tile_index: Res<TileIndex>,
+    ghosts: Query<Entity, With<BlueprintGhost>>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut shown: Local<BlueprintView>,
+) {
+    let config = &*cursor.config;
+    let mut view = BlueprintView::default();
+
+    if let (BuildTool::Blueprint, Some(blueprint), Some(pos)) =
+        (&*tool, library.selected(), cursor.grid_position())
+    {
+        let offset = IVec2::new(pos.x, pos.y);
+        let footprint_of = |item: &ItemSnapshot| {
+            registry
+                .get(&item.id)
+                .map_or_else(Footprint::default, |definition| {
+                    item.facing.rotate_footprint(definition.footprint)
+                })
+        };
+        let center = |anchor: GridPosition| {
+            blueprint
+                .items
+                .iter()
+                .find(|item| item.pos == anchor)
+                .map(|item| {
+                    config
+                        .footprint_center(anchor.offset(offset), footprint_of(item))
+                        .truncate()
+                })
+        };
+
+        for item in &blueprint.items {
+            let footprint = footprint_of(item);
+            let target = item.pos.offset(offset);
+            view.items.push((
+                item.id.clone(),
+                config.footprint_center(target, footprint).truncate(),
+                item.facing.rotation(),
+                tile_index.footprint_blocked(config, target, footprint, &[]),
+            ));
+        }
+        view.wires = blueprint
+            .wires
+            .iter()
+            .filter_map(|wire| Some((center(wire.from)?, center(wire.to)?)))
+            .collect();
+    }
+
+    if view == *shown {
+        return;
+    }
+    for ghost in &ghosts {
+        commands.entity(ghost).despawn();
+    }
+
+    for (id, center, rotation, blocked) in &view.items {
+        let Some(definition) = registry.get(id) else {
+            continue;
+        };
+        GhostStyle::new(definition, &mut meshes, &mut materials).spawn(
+            &mut commands,
+            BlueprintGhost,
+            *center,
+            *rotation,
+            *blocked,
+        );
+    }
+    spawn_ghost_wires(
+        &mut commands,
+        BlueprintGhost,
+        &view.wires,
+        &mut meshes,
+        &mut materials,
+    );
+
+    *shown = view;
+}
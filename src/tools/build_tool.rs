@@ -1,58 +1,168 @@
+use crate::items::definition::ItemId;
+use crate::items::registry::ItemRegistry;
+use crate::tools::blueprint::BlueprintPlugin;
+use crate::tools::drag_place::DragPlacePlugin;
+use crate::tools::fuel_tool::FuelToolPlugin;
+use crate::tools::select_tool::SelectToolPlugin;
 use crate::ui::MainText;
+use crate::wire_system::port::Facing;
+use crate::wire_system::{WireState, wire_tool_label};
 use bevy::prelude::*;
 
 pub struct BuildToolPlugin;
 
 impl Plugin for BuildToolPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(BuildTool::Generator)
+        app.init_resource::<BuildTool>()
+            .init_resource::<PlacementFacing>()
+            .add_plugins((
+                DragPlacePlugin,
+                SelectToolPlugin,
+                BlueprintPlugin,
+                FuelToolPlugin,
+            ))
             .add_systems(Update, build_tool_selector);
     }
 }
 
-#[derive(Resource, PartialEq, Eq, Clone, Copy)]
+#[derive(Resource, PartialEq, Eq, Clone)]
 pub enum BuildTool {
-    Generator,
-    PowerPole,
-    Light,
+    /// Place the registry item with this id
+    Place(ItemId),
     Wire,
+    /// Select placed items to delete, move or copy them together
+    Select,
+    /// Stamp the selected blueprint from the library
+    Blueprint,
+    /// Pour fuel into generators
+    Fuel,
 }
 
-#[derive(Component, Clone, Copy)]
-pub enum TileContent {
-    Generator,
-    PowerPole,
-    Light,
+impl BuildTool {
+    pub fn label(&self, registry: &ItemRegistry, facing: Facing) -> String {
+        match self {
+            BuildTool::Place(id) => {
+                let name = registry
+                    .get(id)
+                    .map_or_else(|| id.to_string(), |definition| definition.name.clone());
+                format!("{} facing {}", name, facing)
+            }
+            BuildTool::Wire => "Wire".into(),
+            BuildTool::Select => "Select".into(),
+            BuildTool::Blueprint => "Blueprint".into(),
+            BuildTool::Fuel => "Fuel".into(),
+        }
+    }
+}
+
+/// Facing given to newly placed items
+#[derive(Resource, Default)]
+pub struct PlacementFacing(pub Facing);
+
+impl FromWorld for BuildTool {
+    fn from_world(world: &mut World) -> Self {
+        // Start with the first registered item, same as pressing its hotkey
+        world
+            .get_resource::<ItemRegistry>()
+            .and_then(|registry| registry.first())
+            .map_or(BuildTool::Wire, |definition| {
+                BuildTool::Place(definition.id.clone())
+            })
+    }
 }
 
+/// Digit key reserved for the wire tool; item hotkeys using it are shadowed
+const WIRE_HOTKEY: KeyCode = KeyCode::Digit4;
+const SELECT_HOTKEY: KeyCode = KeyCode::KeyS;
+/// Turns the item being placed clockwise
+const ROTATE_KEY: KeyCode = KeyCode::KeyR;
+/// Steps through every item, including those without a digit hotkey
+const NEXT_ITEM_KEY: KeyCode = KeyCode::Tab;
+
 fn build_tool_selector(
     keys: Res<ButtonInput<KeyCode>>,
+    registry: Res<ItemRegistry>,
+    wire_state: Res<WireState>,
     mut build_tool: ResMut<BuildTool>,
+    mut facing: ResMut<PlacementFacing>,
     mut text_spans: Query<&mut TextSpan>,
     main_text_entities: Query<&Children, With<MainText>>,
 ) {
-    if keys.just_pressed(KeyCode::Digit1) {
-        *build_tool = BuildTool::Generator;
-        update_tool_text("Generator", &mut text_spans, &main_text_entities);
+    for definition in registry.iter() {
+        let Some(key) = definition.hotkey.and_then(digit_key) else {
+            continue;
+        };
+
+        if key != WIRE_HOTKEY && keys.just_pressed(key) {
+            *build_tool = BuildTool::Place(definition.id.clone());
+            update_tool_text(
+                &build_tool.label(&registry, facing.0),
+                &mut text_spans,
+                &main_text_entities,
+            );
+        }
     }
 
-    if keys.just_pressed(KeyCode::Digit2) {
-        *build_tool = BuildTool::PowerPole;
-        update_tool_text("Power Pole", &mut text_spans, &main_text_entities);
+    if keys.just_pressed(NEXT_ITEM_KEY) {
+        let next = match &*build_tool {
+            BuildTool::Place(id) => registry.after(id),
+            _ => registry.first(),
+        };
+        if let Some(definition) = next {
+            *build_tool = BuildTool::Place(definition.id.clone());
+            update_tool_text(
+                &build_tool.label(&registry, facing.0),
+                &mut text_spans,
+                &main_text_entities,
+            );
+        }
     }
 
-    if keys.just_pressed(KeyCode::Digit3) {
-        *build_tool = BuildTool::Light;
-        update_tool_text("Light", &mut text_spans, &main_text_entities);
+    if keys.just_pressed(ROTATE_KEY) && matches!(*build_tool, BuildTool::Place(_)) {
+        facing.0 = facing.0.next();
+        update_tool_text(
+            &build_tool.label(&registry, facing.0),
+            &mut text_spans,
+            &main_text_entities,
+        );
     }
 
-    if keys.just_pressed(KeyCode::Digit4) {
+    if keys.just_pressed(WIRE_HOTKEY) {
         *build_tool = BuildTool::Wire;
-        update_tool_text("Wire", &mut text_spans, &main_text_entities);
+        update_tool_text(
+            &wire_tool_label(wire_state.kind),
+            &mut text_spans,
+            &main_text_entities,
+        );
+    }
+
+    if keys.just_pressed(SELECT_HOTKEY) {
+        *build_tool = BuildTool::Select;
+        update_tool_text(
+            &build_tool.label(&registry, facing.0),
+            &mut text_spans,
+            &main_text_entities,
+        );
+    }
+}
+
+fn digit_key(digit: u8) -> Option<KeyCode> {
+    match digit {
+        0 => Some(KeyCode::Digit0),
+        1 => Some(KeyCode::Digit1),
+        2 => Some(KeyCode::Digit2),
+        3 => Some(KeyCode::Digit3),
+        4 => Some(KeyCode::Digit4),
+        5 => Some(KeyCode::Digit5),
+        6 => Some(KeyCode::Digit6),
+        7 => Some(KeyCode::Digit7),
+        8 => Some(KeyCode::Digit8),
+        9 => Some(KeyCode::Digit9),
+        _ => None,
     }
 }
 
-fn update_tool_text(
+pub fn update_tool_text(
     tool_name: &str,
     text_spans: &mut Query<&mut TextSpan>,
     main_text_entities: &Query<&Children, With<MainText>>,
@@ -0,0 +1,293 @@
+use crate::items::definition::{ItemDefinition, ItemId};
+use crate::items::registry::ItemRegistry;
+use crate::power::power_network::PowerNetworks;
+use crate::tools::build_tool::{BuildTool, PlacementFacing};
+use crate::tools::ghost::{GhostStyle, spawn_ghost_wires};
+use crate::wire_system::ConnectionPoint;
+use crate::wire_system::auto_wire::{
+    AutoWire, WireCandidate, WireGroup, choose_targets, join_candidates,
+};
+use crate::wire_system::port::Facing;
+use crate::world::grid::{Footprint, GridConfig, GridCursor, GridPosition};
+use crate::world::history;
+use crate::world::tile_index::TileIndex;
+use bevy::prelude::*;
+use std::collections::HashSet;
+
+pub struct DragPlacePlugin;
+
+impl Plugin for DragPlacePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DragPlacement>()
+            .add_systems(Update, (drag_place_input, update_placement_ghosts).chain());
+    }
+}
+
+/// Upper bound on items planned by one drag, so a huge rectangle can't stall a frame
+const MAX_DRAG_PLACEMENTS: usize = 1024;
+
+/// Right-drag with the place tool: lays items along the dominant axis, or over
+/// a rectangle while Shift is held. Nothing is placed until the button is released.
+#[derive(Resource, Default)]
+pub struct DragPlacement {
+    /// Tile the drag started on, `None` while the button is up
+    start: Option<GridPosition>,
+    /// What the ghosts currently show, so they're only rebuilt on change
+    shown: Option<GhostPlan>,
+}
+
+#[derive(PartialEq)]
+struct GhostPlan {
+    id: ItemId,
+    facing: Facing,
+    /// Anchors to place at and whether each one is blocked
+    placements: Vec<(GridPosition, bool)>,
+    /// Wires auto-wiring would run, as world-space end points
+    wires: Vec<(Vec2, Vec2)>,
+}
+
+/// Placed items a pole could be wired to
+type WireTargets<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static GridPosition,
+        Option<&'static Footprint>,
+        &'static ConnectionPoint,
+    ),
+>;
+
+/// Translucent preview of an item about to be placed
+#[derive(Component, Clone)]
+struct PlacementGhost;
+
+#[allow(clippy::too_many_arguments)]
+fn drag_place_input(
+    buttons: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    cursor: GridCursor,
+    tool: Res<BuildTool>,
+    registry: Res<ItemRegistry>,
+    facing: Res<PlacementFacing>,
+    mut drag: ResMut<DragPlacement>,
+    mut commands: Commands,
+) {
+    let BuildTool::Place(id) = &*tool else {
+        drag.start = None;
+        return;
+    };
+
+    if buttons.just_pressed(MouseButton::Right) {
+        drag.start = cursor.grid_position();
+    }
+
+    if !buttons.just_released(MouseButton::Right) {
+        return;
+    }
+    let (Some(start), Some(end), Some(definition)) =
+        (drag.start.take(), cursor.grid_position(), registry.get(id))
+    else {
+        return;
+    };
+
+    let rectangle = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let footprint = facing.0.rotate_footprint(definition.footprint);
+    let positions = plan_positions(start, end, footprint, definition.drag_spacing, rectangle);
+
+    let id = id.clone();
+    let facing = facing.0;
+    commands.queue(move |world: &mut World| {
+        history::place_items_recorded(world, &id, &positions, facing)
+    });
+}
+
+/// Anchors from `start` toward `end`, one footprint (or `spacing` tiles,
+/// whichever is more) apart
+fn plan_positions(
+    start: GridPosition,
+    end: GridPosition,
+    footprint: Footprint,
+    spacing: i32,
+    rectangle: bool,
+) -> Vec<GridPosition> {
+    let step_x = spacing.max(footprint.width).max(1);
+    let step_y = spacing.max(footprint.height).max(1);
+    let steps = |from: i32, to: i32, step: i32| {
+        let direction = if to < from { -1 } else { 1 };
+        (0..=(to - from).abs() / step).map(move |index| from + index * step * direction)
+    };
+
+    let (dx, dy) = (end.x - start.x, end.y - start.y);
+    if rectangle {
+        steps(start.y, end.y, step_y)
+            .flat_map(|y| steps(start.x, end.x, step_x).map(move |x| GridPosition { x, y }))
+            .take(MAX_DRAG_PLACEMENTS)
+            .collect()
+    } else if dx.abs() >= dy.abs() {
+        steps(start.x, end.x, step_x)
+            .map(|x| GridPosition { x, y: start.y })
+            .take(MAX_DRAG_PLACEMENTS)
+            .collect()
+    } else {
+        steps(start.y, end.y, step_y)
+            .map(|y| GridPosition { x: start.x, y })
+            .take(MAX_DRAG_PLACEMENTS)
+            .collect()
+    }
+}
+
+/// Marks each planned anchor blocked if its footprint leaves the grid, hits a
+/// placed item or overlaps an earlier item of the same plan
+fn check_placements(
+    positions: &[GridPosition],
+    footprint: Footprint,
+    config: &GridConfig,
+    tile_index: &TileIndex,
+) -> Vec<(GridPosition, bool)> {
+    let mut claimed: HashSet<GridPosition> = HashSet::new();
+    positions
+        .iter()
+        .map(|anchor| {
+            let blocked = tile_index.footprint_blocked(config, *anchor, footprint, &[])
+                || footprint.tiles(*anchor).any(|tile| claimed.contains(&tile));
+            if !blocked {
+                claimed.extend(footprint.tiles(*anchor));
+            }
+            (*anchor, blocked)
+        })
+        .collect()
+}
+
+/// Wires placing poles at the free `placements` in order would create, each
+/// pole seeing the ones placed before it
+fn plan_auto_wires(
+    definition: &ItemDefinition,
+    placements: &[(GridPosition, bool)],
+    footprint: Footprint,
+    config: &GridConfig,
+    targets: &WireTargets,
+    networks: &PowerNetworks,
+) -> Vec<(Vec2, Vec2)> {
+    let (mut centers, mut candidates): (Vec<Vec2>, Vec<WireCandidate>) = targets
+        .iter()
+        .map(|(entity, pos, target_footprint, point)| {
+            (
+                config
+                    .footprint_center(*pos, target_footprint.copied().unwrap_or_default())
+                    .truncate(),
+                WireCandidate::of(*pos, point, networks, entity),
+            )
+        })
+        .unzip();
+
+    let mut wires = Vec::new();
+    for (index, (anchor, _)) in placements
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, blocked))| !blocked)
+    {
+        let center = config.footprint_center(*anchor, footprint).truncate();
+        let chosen = choose_targets(
+            *anchor,
+            definition.reach,
+            definition.max_connections,
+            &candidates,
+        );
+        wires.extend(chosen.iter().map(|target| (center, centers[*target])));
+        join_candidates(
+            &mut candidates,
+            &chosen,
+            WireCandidate {
+                pos: *anchor,
+                reach: definition.reach,
+                free: definition.max_connections as usize,
+                group: WireGroup::Planned(index),
+            },
+        );
+        centers.push(center);
+    }
+    wires
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_placement_ghosts(
+    keys: Res<ButtonInput<KeyCode>>,
+    cursor: GridCursor,
+    tool: Res<BuildTool>,
+    registry: Res<ItemRegistry>,
+    facing: Res<PlacementFacing>,
+    tile_index: Res<TileIndex>,
+    auto_wire: Res<AutoWire>,
+    targets: WireTargets,
+    networks: Res<PowerNetworks>,
+    mut drag: ResMut<DragPlacement>,
+    ghosts: Query<Entity, With<PlacementGhost>>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let config = &*cursor.config;
+    let plan = match (&*tool, cursor.grid_position()) {
+        (BuildTool::Place(id), Some(end)) => registry.get(id).map(|definition| {
+            let footprint = facing.0.rotate_footprint(definition.footprint);
+            // Without a drag the ghost just follows the cursor
+            let start = drag.start.unwrap_or(end);
+            let rectangle = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+            let positions =
+                plan_positions(start, end, footprint, definition.drag_spacing, rectangle);
+            let placements = check_placements(&positions, footprint, config, &tile_index);
+            let wires = if auto_wire.enabled && definition.pole {
+                plan_auto_wires(
+                    definition,
+                    &placements,
+                    footprint,
+                    config,
+                    &targets,
+                    &networks,
+                )
+            } else {
+                Vec::new()
+            };
+            GhostPlan {
+                id: id.clone(),
+                facing: facing.0,
+                placements,
+                wires,
+            }
+        }),
+        _ => None,
+    };
+
+    if plan == drag.shown {
+        return;
+    }
+    for ghost in &ghosts {
+        commands.entity(ghost).despawn();
+    }
+
+    let shown = plan
+        .as_ref()
+        .and_then(|plan| Some((plan, registry.get(&plan.id)?)));
+    if let Some((plan, definition)) = shown {
+        let footprint = plan.facing.rotate_footprint(definition.footprint);
+        let style = GhostStyle::new(definition, &mut meshes, &mut materials);
+        for (anchor, blocked) in &plan.placements {
+            style.spawn(
+                &mut commands,
+                PlacementGhost,
+                config.footprint_center(*anchor, footprint).truncate(),
+                plan.facing.rotation(),
+                *blocked,
+            );
+        }
+        spawn_ghost_wires(
+            &mut commands,
+            PlacementGhost,
+            &plan.wires,
+            &mut meshes,
+            &mut materials,
+        );
+    }
+    drag.shown = plan;
+}
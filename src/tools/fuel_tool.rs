@@ -0,0 +1,92 @@
+use crate::items::generator::{FuelType, Generator};
+use crate::tools::build_tool::{BuildTool, update_tool_text};
+use crate::ui::MainText;
+use crate::world::grid::GridCursor;
+use crate::world::tile_index::TileIndex;
+use bevy::prelude::*;
+
+pub struct FuelToolPlugin;
+
+impl Plugin for FuelToolPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FuelState>()
+            .add_systems(Update, fuel_tool_input);
+    }
+}
+
+const FUEL_HOTKEY: KeyCode = KeyCode::KeyF;
+/// Cycles the fuel type while the fuel tool is active
+const FUEL_TYPE_KEY: KeyCode = KeyCode::KeyQ;
+/// Units poured into a generator per click
+const FUEL_PER_CLICK: f32 = 5.0;
+
+/// Fuel the fuel tool pours
+#[derive(Resource, Default)]
+pub struct FuelState {
+    pub fuel_type: FuelType,
+}
+
+pub fn fuel_tool_label(fuel_type: FuelType) -> String {
+    format!(
+        "Fuel ({}, {:.0} J/unit, {:.1}s burn)",
+        fuel_type.name(),
+        fuel_type.energy(),
+        fuel_type.burn_time()
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn fuel_tool_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    cursor: GridCursor,
+    tile_index: Res<TileIndex>,
+    mut tool: ResMut<BuildTool>,
+    mut state: ResMut<FuelState>,
+    mut generators: Query<&mut Generator>,
+    mut text_spans: Query<&mut TextSpan>,
+    main_text_entities: Query<&Children, With<MainText>>,
+) {
+    if keys.just_pressed(FUEL_HOTKEY) {
+        *tool = BuildTool::Fuel;
+        update_tool_text(
+            &fuel_tool_label(state.fuel_type),
+            &mut text_spans,
+            &main_text_entities,
+        );
+    }
+    if *tool != BuildTool::Fuel {
+        return;
+    }
+
+    if keys.just_pressed(FUEL_TYPE_KEY) {
+        state.fuel_type = state.fuel_type.next();
+        update_tool_text(
+            &fuel_tool_label(state.fuel_type),
+            &mut text_spans,
+            &main_text_entities,
+        );
+    }
+
+    if !buttons.just_pressed(MouseButton::Right) {
+        return;
+    }
+    let Some(mut generator) = cursor
+        .grid_position()
+        .and_then(|pos| tile_index.item(pos))
+        .and_then(|item| generators.get_mut(item).ok())
+    else {
+        return;
+    };
+
+    match generator.refuel(state.fuel_type, FUEL_PER_CLICK) {
+        Ok(added) => println!(
+            "Added {} {}, tank at {}/{}",
+            added,
+            state.fuel_type.name(),
+            generator.fuel_amount,
+            generator.tank_capacity
+        ),
+        Err(reason) => println!("Cannot refuel: {}", reason),
+    }
+}
@@ -0,0 +1,77 @@
+use crate::items::definition::ItemDefinition;
+use crate::items::registry::{item_color, item_mesh};
+use bevy::color::palettes::basic::RED;
+use bevy::prelude::*;
+
+const GHOST_ALPHA: f32 = 0.4;
+const GHOST_WIRE_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.5);
+const GHOST_WIRE_WIDTH: f32 = 1.5;
+/// Above placed items so blocked spots stay visible
+const GHOST_Z: f32 = 2.0;
+
+/// Mesh and materials for translucent previews of one item, shared by all of
+/// its ghosts: tinted like the item, or red where it can't go
+pub struct GhostStyle {
+    mesh: Handle<Mesh>,
+    free: Handle<ColorMaterial>,
+    blocked: Handle<ColorMaterial>,
+}
+
+impl GhostStyle {
+    pub fn new(
+        definition: &ItemDefinition,
+        meshes: &mut Assets<Mesh>,
+        materials: &mut Assets<ColorMaterial>,
+    ) -> Self {
+        Self {
+            mesh: item_mesh(&definition.shape, meshes),
+            free: materials.add(ColorMaterial::from_color(
+                item_color(definition).with_alpha(GHOST_ALPHA),
+            )),
+            blocked: materials.add(ColorMaterial::from_color(
+                Color::from(RED).with_alpha(GHOST_ALPHA),
+            )),
+        }
+    }
+
+    pub fn spawn(
+        &self,
+        commands: &mut Commands,
+        marker: impl Bundle,
+        translation: Vec2,
+        rotation: Quat,
+        blocked: bool,
+    ) {
+        commands.spawn((
+            marker,
+            Mesh2d(self.mesh.clone()),
+            MeshMaterial2d(if blocked {
+                self.blocked.clone()
+            } else {
+                self.free.clone()
+            }),
+            Transform::from_translation(translation.extend(GHOST_Z)).with_rotation(rotation),
+        ));
+    }
+}
+
+/// Previews of wires about to be run, as world-space end points
+pub fn spawn_ghost_wires<M: Bundle + Clone>(
+    commands: &mut Commands,
+    marker: M,
+    wires: &[(Vec2, Vec2)],
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+) {
+    let material = materials.add(ColorMaterial::from_color(GHOST_WIRE_COLOR));
+    for (from, to) in wires {
+        let direction = *to - *from;
+        commands.spawn((
+            marker.clone(),
+            Mesh2d(meshes.add(Rectangle::new(direction.length(), GHOST_WIRE_WIDTH))),
+            MeshMaterial2d(material.clone()),
+            Transform::from_translation(((*from + *to) / 2.0).extend(GHOST_Z))
+                .with_rotation(Quat::from_rotation_z(direction.y.atan2(direction.x))),
+        ));
+    }
+}
@@ -0,0 +1,300 @@
+use crate::items::definition::ItemId;
+use crate::items::registry::{Item, ItemRegistry};
+use crate::tools::build_tool::BuildTool;
+use crate::tools::ghost::GhostStyle;
+use crate::wire_system::port::Facing;
+use crate::wire_system::{Wire, WireEnds, wire_end};
+use crate::world::grid::{Footprint, GridConfig, GridCursor, GridPosition};
+use crate::world::history;
+use crate::world::tile_index::TileIndex;
+use bevy::prelude::*;
+
+pub struct SelectToolPlugin;
+
+impl Plugin for SelectToolPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Selection>()
+            .add_systems(Update, (select_input, update_selection_visuals).chain());
+    }
+}
+
+const DELETE_KEYS: [KeyCode; 2] = [KeyCode::Delete, KeyCode::Backspace];
+/// Picks the selection up to move it; right-click drops it
+const MOVE_KEY: KeyCode = KeyCode::KeyM;
+/// Like move, but leaves the originals in place
+const COPY_KEY: KeyCode = KeyCode::KeyC;
+
+const SELECTION_COLOR: Color = Color::srgb(0.3, 0.7, 1.0);
+const OUTLINE_WIDTH: f32 = 1.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Shift {
+    Move,
+    Copy,
+}
+
+/// Items picked with the select tool by right-dragging a rectangle. Wires
+/// between two selected items count as selected too.
+#[derive(Resource, Default)]
+pub struct Selection {
+    pub items: Vec<Entity>,
+    /// Tile the selection rectangle started on
+    drag_start: Option<GridPosition>,
+    /// Pending move or copy, with the tile the selection was grabbed at
+    grab: Option<(Shift, GridPosition)>,
+}
+
+impl Selection {
+    fn is_idle(&self) -> bool {
+        self.items.is_empty() && self.drag_start.is_none() && self.grab.is_none()
+    }
+}
+
+/// Outline, wire highlight or ghost drawn for the selection
+#[derive(Component)]
+struct SelectionVisual;
+
+/// Everything the selection visuals show, so they're only rebuilt on change
+#[derive(Default, PartialEq)]
+struct SelectionView {
+    outlines: Vec<Rect>,
+    wires: Vec<(Vec2, Vec2)>,
+    /// Item, position, rotation and whether it's blocked there
+    ghosts: Vec<(ItemId, Vec2, Quat, bool)>,
+}
+
+fn select_input(
+    buttons: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    cursor: GridCursor,
+    tool: Res<BuildTool>,
+    mut selection: ResMut<Selection>,
+    items: Query<(Entity, &GridPosition, &Footprint), With<Item>>,
+    mut commands: Commands,
+) {
+    if *tool != BuildTool::Select {
+        if !selection.is_idle() {
+            *selection = Selection::default();
+        }
+        return;
+    }
+    if selection.items.iter().any(|item| !items.contains(*item)) {
+        selection.items.retain(|item| items.contains(*item));
+    }
+    let Some(cursor_pos) = cursor.grid_position() else {
+        return;
+    };
+
+    for (key, shift) in [(MOVE_KEY, Shift::Move), (COPY_KEY, Shift::Copy)] {
+        if keys.just_pressed(key) {
+            // Pressing the same key again puts the selection back down
+            selection.grab = match selection.grab {
+                Some((current, _)) if current == shift => None,
+                _ if selection.items.is_empty() => None,
+                _ => Some((shift, cursor_pos)),
+            };
+        }
+    }
+
+    if keys.any_just_pressed(DELETE_KEYS) && !selection.items.is_empty() {
+        let doomed = std::mem::take(&mut selection.items);
+        selection.grab = None;
+        println!("Removing {} selected items", doomed.len());
+        commands.queue(move |world: &mut World| history::remove_items_recorded(world, &doomed));
+    }
+
+    if buttons.just_pressed(MouseButton::Right) {
+        match selection.grab.take() {
+            Some((shift, grabbed)) => {
+                let offset = IVec2::new(cursor_pos.x - grabbed.x, cursor_pos.y - grabbed.y);
+                if shift == Shift::Move && offset == IVec2::ZERO {
+                    return;
+                }
+                let items = selection.items.clone();
+                commands.queue(move |world: &mut World| {
+                    let shifted =
+                        history::shift_items_recorded(world, &items, offset, shift == Shift::Copy);
+                    // The selection follows the items to their new place
+                    if !shifted.is_empty() {
+                        world.resource_mut::<Selection>().items = shifted;
+                    }
+                });
+            }
+            None => selection.drag_start = Some(cursor_pos),
+        }
+    }
+
+    if buttons.just_released(MouseButton::Right)
+        && let Some(start) = selection.drag_start.take()
+    {
+        let (min, max) = tile_corners(start, cursor_pos);
+        selection.items = items
+            .iter()
+            .filter(|(_, pos, footprint)| {
+                pos.x <= max.x
+                    && pos.y <= max.y
+                    && pos.x + footprint.width > min.x
+                    && pos.y + footprint.height > min.y
+            })
+            .map(|(item, _, _)| item)
+            .collect();
+        println!("Selected {} items", selection.items.len());
+    }
+}
+
+/// Lower-left and upper-right tiles of the rectangle spanned by `a` and `b`
+fn tile_corners(a: GridPosition, b: GridPosition) -> (IVec2, IVec2) {
+    let (a, b) = (IVec2::new(a.x, a.y), IVec2::new(b.x, b.y));
+    (a.min(b), a.max(b))
+}
+
+/// World-space rectangle covered by `footprint` anchored at `pos`
+fn footprint_rect(config: &GridConfig, pos: GridPosition, footprint: Footprint) -> Rect {
+    let size = Vec2::new(footprint.width as f32, footprint.height as f32) * config.tile_size;
+    Rect::from_center_size(config.footprint_center(pos, footprint).truncate(), size)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_selection_visuals(
+    cursor: GridCursor,
+    selection: Res<Selection>,
+    items: Query<(&Item, &GridPosition, &Footprint, Option<&Facing>)>,
+    wires: Query<&Wire>,
+    ends: WireEnds,
+    registry: Res<ItemRegistry>,
+    tile_index: Res<TileIndex>,
+    visuals: Query<Entity, With<SelectionVisual>>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut shown: Local<SelectionView>,
+) {
+    let config = &*cursor.config;
+    let cursor_pos = cursor.grid_position();
+    let mut view = SelectionView::default();
+
+    for item in &selection.items {
+        if let Ok((_, pos, footprint, _)) = items.get(*item) {
+            view.outlines.push(footprint_rect(config, *pos, *footprint));
+        }
+    }
+    if let (Some(start), Some(end)) = (selection.drag_start, cursor_pos) {
+        let (min, max) = tile_corners(start, end);
+        let footprint = Footprint {
+            width: max.x - min.x + 1,
+            height: max.y - min.y + 1,
+        };
+        view.outlines.push(footprint_rect(
+            config,
+            GridPosition { x: min.x, y: min.y },
+            footprint,
+        ));
+    }
+
+    view.wires = wires
+        .iter()
+        .filter(|wire| selection.items.contains(&wire.from) && selection.items.contains(&wire.to))
+        .filter_map(|wire| {
+            Some((
+                wire_end(&ends, config, wire.from, wire.from_port)?,
+                wire_end(&ends, config, wire.to, wire.to_port)?,
+            ))
+        })
+        .collect();
+
+    if let (Some((shift, grabbed)), Some(end)) = (selection.grab, cursor_pos) {
+        let offset = IVec2::new(end.x - grabbed.x, end.y - grabbed.y);
+        for item in &selection.items {
+            let Ok((Item(id), pos, footprint, facing)) = items.get(*item) else {
+                continue;
+            };
+            let target = pos.offset(offset);
+            // Moved items may land on tiles the selection itself leaves
+            let ignore: &[Entity] = match shift {
+                Shift::Move => &selection.items,
+                Shift::Copy => &[],
+            };
+            let blocked = tile_index.footprint_blocked(config, target, *footprint, ignore);
+            view.ghosts.push((
+                id.clone(),
+                config.footprint_center(target, *footprint).truncate(),
+                facing.copied().unwrap_or_default().rotation(),
+                blocked,
+            ));
+        }
+    }
+
+    if view == *shown {
+        return;
+    }
+    for visual in &visuals {
+        commands.entity(visual).despawn();
+    }
+
+    let highlight = materials.add(ColorMaterial::from_color(SELECTION_COLOR));
+    for rect in &view.outlines {
+        spawn_outline(&mut commands, &mut meshes, &highlight, *rect);
+    }
+    for (from, to) in &view.wires {
+        let direction = *to - *from;
+        commands.spawn((
+            SelectionVisual,
+            Mesh2d(meshes.add(Rectangle::new(direction.length(), OUTLINE_WIDTH))),
+            MeshMaterial2d(highlight.clone()),
+            Transform::from_translation(((*from + *to) / 2.0).extend(3.0))
+                .with_rotation(Quat::from_rotation_z(direction.y.atan2(direction.x))),
+        ));
+    }
+
+    for (id, center, rotation, blocked) in &view.ghosts {
+        let Some(definition) = registry.get(id) else {
+            continue;
+        };
+        GhostStyle::new(definition, &mut meshes, &mut materials).spawn(
+            &mut commands,
+            SelectionVisual,
+            *center,
+            *rotation,
+            *blocked,
+        );
+    }
+
+    *shown = view;
+}
+
+/// Four thin edges around `rect`, the same way the hover border is drawn
+fn spawn_outline(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    material: &Handle<ColorMaterial>,
+    rect: Rect,
+) {
+    let center = rect.center();
+    let size = rect.size();
+    let edges = [
+        (
+            Vec2::new(center.x, rect.max.y),
+            Vec2::new(size.x, OUTLINE_WIDTH),
+        ),
+        (
+            Vec2::new(center.x, rect.min.y),
+            Vec2::new(size.x, OUTLINE_WIDTH),
+        ),
+        (
+            Vec2::new(rect.min.x, center.y),
+            Vec2::new(OUTLINE_WIDTH, size.y),
+        ),
+        (
+            Vec2::new(rect.max.x, center.y),
+            Vec2::new(OUTLINE_WIDTH, size.y),
+        ),
+    ];
+    for (position, edge_size) in edges {
+        commands.spawn((
+            SelectionVisual,
+            Mesh2d(meshes.add(Rectangle::from_size(edge_size))),
+            MeshMaterial2d(material.clone()),
+            Transform::from_translation(position.extend(3.0)),
+        ));
+    }
+}
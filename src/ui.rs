@@ -1,4 +1,5 @@
-use crate::tools::build_tool::BuildTool;
+use crate::items::registry::ItemRegistry;
+use crate::tools::build_tool::{BuildTool, PlacementFacing};
 use bevy::prelude::*;
 
 pub struct UiPlugin;
@@ -12,13 +13,13 @@ impl Plugin for UiPlugin {
     }
 }
 
-fn setup(mut commands: Commands, tool: Res<BuildTool>) {
-    let tool_name: &str = match *tool {
-        BuildTool::Generator => "Generator",
-        BuildTool::PowerPole => "Power Pole",
-        BuildTool::Light => "Light",
-        BuildTool::Wire => "Wire",
-    };
+fn setup(
+    mut commands: Commands,
+    tool: Res<BuildTool>,
+    facing: Res<PlacementFacing>,
+    registry: Res<ItemRegistry>,
+) {
+    let tool_name = tool.label(&registry, facing.0);
 
     commands
         .spawn((
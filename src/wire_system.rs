@@ -1,34 +1,86 @@
-use crate::tools::build_tool::BuildTool;
-use crate::world::grid::{grid_to_world, GridPosition};
+pub mod auto_wire;
+pub mod port;
+
+use crate::tools::build_tool::{BuildTool, update_tool_text};
+use crate::ui::MainText;
+use crate::world::edit;
+use crate::world::grid::{Footprint, GridConfig, GridCursor, GridPosition};
+use crate::world::tile_index::TileIndex;
+use auto_wire::AutoWirePlugin;
 use bevy::prelude::*;
 use bevy::sprite::MeshMaterial2d;
-use std::collections::HashSet;
+use port::{Ports, pick_ports, port_offset};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 
 pub struct WireSystemPlugin;
 
 impl Plugin for WireSystemPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            (
-                wire_visual_system,
-                wire_preview_system,
-                cleanup_orphaned_wires,
-            ),
-        );
+        app.init_resource::<WireIndex>()
+            .add_plugins(AutoWirePlugin)
+            .add_observer(on_wire_added)
+            .add_observer(on_wire_removed)
+            .add_systems(PostStartup, merge_duplicate_wires)
+            .add_systems(
+                Update,
+                (
+                    wire_visual_system,
+                    wire_preview_system,
+                    wire_hover_system,
+                    wire_kind_selector,
+                    cleanup_orphaned_wires,
+                ),
+            );
     }
 }
 
 #[derive(Component)]
 pub struct ConnectionPoint {
     pub max_connections: u8,
+    /// Longest wire, in tiles, that may be attached here
+    pub reach: f32,
     pub connections: HashSet<Entity>,
 }
 
+/// Why a wire can't be run between two connection points
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConnectionError {
+    Missing,
+    SameItem,
+    AlreadyConnected,
+    AtCapacity,
+    OutOfReach { distance: f32, reach: f32 },
+    NoFreePort,
+    PortMismatch,
+}
+
+impl fmt::Display for ConnectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionError::Missing => write!(f, "endpoint has no connection point"),
+            ConnectionError::SameItem => write!(f, "cannot wire an item to itself"),
+            ConnectionError::AlreadyConnected => write!(f, "items are already wired together"),
+            ConnectionError::AtCapacity => write!(f, "connection points at capacity"),
+            ConnectionError::OutOfReach { distance, reach } => write!(
+                f,
+                "out of reach ({:.1} tiles, limit {:.1})",
+                distance, reach
+            ),
+            ConnectionError::NoFreePort => write!(f, "no free port left"),
+            ConnectionError::PortMismatch => {
+                write!(f, "ports don't match (input to input or output to output)")
+            }
+        }
+    }
+}
+
 impl ConnectionPoint {
-    pub fn new(max_connections: u8) -> Self {
+    pub fn new(max_connections: u8, reach: f32) -> Self {
         Self {
             max_connections,
+            reach,
             connections: HashSet::new(),
         }
     }
@@ -37,6 +89,27 @@ impl ConnectionPoint {
         self.connections.len() < self.max_connections as usize
     }
 
+    /// Checks a wire from this point at `pos` to `other` at `other_pos`.
+    /// The shorter of the two reaches limits the wire.
+    pub fn check_wire(
+        &self,
+        pos: GridPosition,
+        other: &ConnectionPoint,
+        other_pos: GridPosition,
+    ) -> Result<(), ConnectionError> {
+        if !self.can_connect() || !other.can_connect() {
+            return Err(ConnectionError::AtCapacity);
+        }
+
+        let reach = self.reach.min(other.reach);
+        let distance = tile_distance(pos, other_pos);
+        if distance > reach {
+            return Err(ConnectionError::OutOfReach { distance, reach });
+        }
+
+        Ok(())
+    }
+
     pub fn add_connection(&mut self, wire_entity: Entity) -> bool {
         if self.can_connect() {
             self.connections.insert(wire_entity);
@@ -51,15 +124,178 @@ impl ConnectionPoint {
     }
 }
 
+/// Straight-line distance between two tiles, in tiles
+pub fn tile_distance(a: GridPosition, b: GridPosition) -> f32 {
+    Vec2::new((a.x - b.x) as f32, (a.y - b.y) as f32).length()
+}
+
 #[derive(Component)]
 pub struct Wire {
     pub from: Entity,
     pub to: Entity,
+    pub kind: WireKind,
+    /// Port the wire attaches to on each end, `None` for items without ports
+    pub from_port: Option<u8>,
+    pub to_port: Option<u8>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WireKind {
+    #[default]
+    Copper,
+    Aluminium,
+    HighVoltage,
+}
+
+impl WireKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            WireKind::Copper => "Copper",
+            WireKind::Aluminium => "Aluminium",
+            WireKind::HighVoltage => "High Voltage",
+        }
+    }
+
+    /// Most wattage the wire can carry
+    pub fn throughput(self) -> f32 {
+        match self {
+            WireKind::Copper => 100.0,
+            WireKind::Aluminium => 150.0,
+            WireKind::HighVoltage => 1000.0,
+        }
+    }
+
+    /// Fraction of the carried power lost per tile of wire
+    pub fn loss_per_tile(self) -> f32 {
+        match self {
+            WireKind::Copper => 0.01,
+            WireKind::Aluminium => 0.03,
+            WireKind::HighVoltage => 0.002,
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            // Yellow/gold wire
+            WireKind::Copper => Color::srgb(1.0, 0.8, 0.0),
+            WireKind::Aluminium => Color::srgb(0.75, 0.75, 0.8),
+            WireKind::HighVoltage => Color::srgb(0.3, 0.6, 1.0),
+        }
+    }
+
+    /// Wire thickness in pixels
+    pub fn thickness(self) -> f32 {
+        match self {
+            WireKind::Copper | WireKind::Aluminium => 2.0,
+            WireKind::HighVoltage => 3.0,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            WireKind::Copper => WireKind::Aluminium,
+            WireKind::Aluminium => WireKind::HighVoltage,
+            WireKind::HighVoltage => WireKind::Copper,
+        }
+    }
+}
+
+/// Looks up the wire between two items, whichever way round it was drawn
+#[derive(Resource, Default)]
+pub struct WireIndex {
+    wires: HashMap<(Entity, Entity), Entity>,
+}
+
+impl WireIndex {
+    pub fn get(&self, a: Entity, b: Entity) -> Option<Entity> {
+        self.wires.get(&pair_key(a, b)).copied()
+    }
+
+    pub fn contains(&self, a: Entity, b: Entity) -> bool {
+        self.wires.contains_key(&pair_key(a, b))
+    }
+}
+
+fn pair_key(a: Entity, b: Entity) -> (Entity, Entity) {
+    (a.min(b), a.max(b))
+}
+
+fn on_wire_added(
+    trigger: Trigger<OnAdd, Wire>,
+    wires: Query<&Wire>,
+    mut ports: Query<&mut Ports>,
+    mut index: ResMut<WireIndex>,
+) {
+    if let Ok(wire) = wires.get(trigger.target()) {
+        index
+            .wires
+            .entry(pair_key(wire.from, wire.to))
+            .or_insert(trigger.target());
+
+        for (item, port) in [(wire.from, wire.from_port), (wire.to, wire.to_port)] {
+            if let (Ok(mut item_ports), Some(port)) = (ports.get_mut(item), port) {
+                item_ports.attach(port, trigger.target());
+            }
+        }
+    }
+}
+
+fn on_wire_removed(
+    trigger: Trigger<OnRemove, Wire>,
+    wires: Query<&Wire>,
+    mut ports: Query<&mut Ports>,
+    mut index: ResMut<WireIndex>,
+) {
+    if let Ok(wire) = wires.get(trigger.target()) {
+        for item in [wire.from, wire.to] {
+            if let Ok(mut item_ports) = ports.get_mut(item) {
+                item_ports.detach(trigger.target());
+            }
+        }
+
+        let key = pair_key(wire.from, wire.to);
+        // A stacked duplicate going away must not drop the entry of the wire that stays
+        if index.wires.get(&key) == Some(&trigger.target()) {
+            index.wires.remove(&key);
+        }
+    }
+}
+
+/// Repairs worlds that picked up stacked wires between the same pair of items,
+/// keeping one wire per pair and freeing the connection slots of the rest
+pub fn merge_duplicate_wires(world: &mut World) {
+    // The indexed wire of each pair is the one that stays
+    let mut wires = world.query::<(Entity, &Wire)>();
+    let index = world.resource::<WireIndex>();
+    let duplicates: Vec<Entity> = wires
+        .iter(world)
+        .filter(|(wire_entity, wire)| {
+            index
+                .get(wire.from, wire.to)
+                .is_some_and(|kept| kept != *wire_entity)
+        })
+        .map(|(wire_entity, _)| wire_entity)
+        .collect();
+
+    if duplicates.is_empty() {
+        return;
+    }
+
+    for wire_entity in &duplicates {
+        edit::remove_wire(world, *wire_entity);
+    }
+    println!("Merged {} duplicate wires", duplicates.len());
 }
 
 #[derive(Component)]
 pub struct WireVisual;
 
+/// How close, in pixels, the cursor has to be to a wire to pick it
+pub const WIRE_PICK_DISTANCE: f32 = 4.0;
+
+// Wire about to be removed by the wire tool
+const WIRE_HOVER_COLOR: Color = Color::srgb(1.0, 0.2, 0.2);
+
 #[derive(Component)]
 pub struct WirePreview;
 
@@ -68,21 +304,89 @@ pub struct WireState {
     pub selected_connection: Option<Entity>,
     pub selected_position: Option<Vec3>,
     pub preview_entity: Option<Entity>,
+    /// Kind of wire the wire tool lays down
+    pub kind: WireKind,
+}
+
+/// Cycles the wire kind while the wire tool is active
+const WIRE_KIND_KEY: KeyCode = KeyCode::KeyQ;
+
+/// Item placement needed to find where its wires attach
+pub type WireEnds<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static GridPosition,
+        Option<&'static Footprint>,
+        Option<&'static Ports>,
+    ),
+>;
+
+/// World position a wire attaches to on `item`: the given port, or the centre
+/// of the footprint for items without ports
+pub fn wire_end(
+    ends: &WireEnds,
+    config: &GridConfig,
+    item: Entity,
+    port: Option<u8>,
+) -> Option<Vec2> {
+    let (pos, footprint, ports) = ends.get(item).ok()?;
+    let footprint = footprint.copied().unwrap_or_default();
+    Some(
+        config.footprint_center(*pos, footprint).truncate()
+            + port_offset(ports, port, footprint, config.tile_size),
+    )
+}
+
+/// Checks a wire between two items and picks the ports it attaches to
+pub fn check_connection(
+    from: Entity,
+    to: Entity,
+    connection_points: &Query<&mut ConnectionPoint>,
+    wire_index: &WireIndex,
+    ends: &WireEnds,
+    config: &GridConfig,
+) -> Result<(Option<u8>, Option<u8>), ConnectionError> {
+    if from == to {
+        return Err(ConnectionError::SameItem);
+    }
+    if wire_index.contains(from, to) {
+        return Err(ConnectionError::AlreadyConnected);
+    }
+
+    let (Ok(from_conn), Ok(to_conn), Ok((from_pos, _, from_ports)), Ok((to_pos, _, to_ports))) = (
+        connection_points.get(from),
+        connection_points.get(to),
+        ends.get(from),
+        ends.get(to),
+    ) else {
+        return Err(ConnectionError::Missing);
+    };
+    from_conn.check_wire(*from_pos, to_conn, *to_pos)?;
+
+    let (Some(from_center), Some(to_center)) = (
+        wire_end(ends, config, from, None),
+        wire_end(ends, config, to, None),
+    ) else {
+        return Err(ConnectionError::Missing);
+    };
+    pick_ports(from_ports, from_center, to_ports, to_center)
 }
 
 fn wire_visual_system(
     mut commands: Commands,
     new_wires: Query<(Entity, &Wire), (With<Wire>, Without<WireVisual>)>,
-    positions: Query<&GridPosition>,
+    ends: WireEnds,
+    config: Res<GridConfig>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
     for (wire_entity, wire) in new_wires.iter() {
         // Get positions of connected entities
-        if let (Ok(from_pos), Ok(to_pos)) = (positions.get(wire.from), positions.get(wire.to)) {
-            let from_world = grid_to_world(*from_pos);
-            let to_world = grid_to_world(*to_pos);
-
+        if let (Some(from_world), Some(to_world)) = (
+            wire_end(&ends, &config, wire.from, wire.from_port),
+            wire_end(&ends, &config, wire.to, wire.to_port),
+        ) {
             // Calculate wire position and rotation
             let wire_center = (from_world + to_world) / 2.0;
             let direction = to_world - from_world;
@@ -90,16 +394,13 @@ fn wire_visual_system(
             let angle = direction.y.atan2(direction.x);
 
             // Create wire visual
-            let wire_material =
-            // Yellow/gold wire
-                materials.add(ColorMaterial::from_color(Color::srgb(1.0, 0.8, 0.0)));
-            // 2 pixel thick wire
-            let wire_mesh = meshes.add(Rectangle::new(length, 2.0));
+            let wire_material = materials.add(ColorMaterial::from_color(wire.kind.color()));
+            let wire_mesh = meshes.add(Rectangle::new(length, wire.kind.thickness()));
 
             commands.entity(wire_entity).insert((
                 Mesh2d(wire_mesh),
                 MeshMaterial2d(wire_material),
-                Transform::from_translation(wire_center + Vec3::new(0.0, 0.0, 0.05))
+                Transform::from_translation(wire_center.extend(0.05))
                     .with_rotation(Quat::from_rotation_z(angle)),
                 WireVisual,
             ));
@@ -110,12 +411,13 @@ fn wire_visual_system(
 fn wire_preview_system(
     mut commands: Commands,
     mut wire_state: ResMut<WireState>,
-    windows: Query<&Window>,
-    camera: Query<(&Camera, &GlobalTransform)>,
-    positions: Query<&GridPosition>,
+    cursor: GridCursor,
+    ends: WireEnds,
+    connection_points: Query<(&ConnectionPoint, &GridPosition)>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     build_tool: Res<BuildTool>,
+    tile_index: Res<TileIndex>,
     existing_previews: Query<Entity, With<WirePreview>>,
     all_entities: Query<Entity>,
 ) {
@@ -132,52 +434,89 @@ fn wire_preview_system(
         return;
     }
 
-    let Ok((camera, camera_transform)) = camera.single() else {
-        return;
-    };
-
-    let Ok(window) = windows.single() else {
-        return;
-    };
-
     // If we have a selected connection, show preview
     if let Some(selected_entity) = wire_state.selected_connection {
         // Validate that the selected entity still exists
-        if positions.get(selected_entity).is_err() {
+        if ends.get(selected_entity).is_err() {
             // Selected entity no longer exists, clear the selection
             wire_state.selected_connection = None;
             wire_state.selected_position = None;
             return;
         }
 
-        if let Some(cursor_pos) = window.cursor_position() {
-            let world_pos = camera
-                .viewport_to_world_2d(camera_transform, cursor_pos)
-                .unwrap_or(Vec2::ZERO);
+        if let Some(world_pos) = cursor.world_position() {
+            let config = &*cursor.config;
+            if let Some(selected_center) = wire_end(&ends, config, selected_entity, None) {
+                let Ok((selected_point, _)) = connection_points.get(selected_entity) else {
+                    return;
+                };
+                let hovered = tile_index
+                    .item(config.world_to_grid(world_pos))
+                    .filter(|item| *item != selected_entity);
+                let item_ports = |item: Entity| ends.get(item).ok().and_then(|(_, _, ports)| ports);
+                let facing_cursor = item_ports(selected_entity)
+                    .and_then(|ports| ports.nearest_free(world_pos - selected_center));
 
-            if let Ok(selected_grid_pos) = positions.get(selected_entity) {
-                let selected_world_pos = grid_to_world(*selected_grid_pos);
-                let direction = Vec2::new(world_pos.x, world_pos.y)
-                    - Vec2::new(selected_world_pos.x, selected_world_pos.y);
+                // Snap to the ports the wire would use: on both ends when
+                // hovering an item, otherwise the selected port facing the cursor
+                let hovered_end =
+                    hovered.and_then(|item| Some((item, wire_end(&ends, config, item, None)?)));
+                let (start_port, target, mismatch) = match hovered_end {
+                    Some((item, hovered_center)) => match pick_ports(
+                        item_ports(selected_entity),
+                        selected_center,
+                        item_ports(item),
+                        hovered_center,
+                    ) {
+                        Ok((from_port, to_port)) => (
+                            from_port,
+                            wire_end(&ends, config, item, to_port).unwrap_or(world_pos),
+                            false,
+                        ),
+                        Err(_) => (facing_cursor, world_pos, true),
+                    },
+                    None => (facing_cursor, world_pos, false),
+                };
+                let selected_world_pos =
+                    wire_end(&ends, config, selected_entity, start_port).unwrap_or(selected_center);
+                let direction = target - selected_world_pos;
 
-                // Limit to 500px
-                let limited_direction = if direction.length() > 500.0 {
-                    direction.normalize() * 500.0
+                // Limit to the reach of the selected item, or of the pair when
+                // hovering another item, measured between anchors the same way
+                // `ConnectionPoint::check_wire` does so the preview matches
+                let reach = hovered
+                    .and_then(|item| connection_points.get(item).ok())
+                    .map_or(selected_point.reach, |(point, _)| {
+                        selected_point.reach.min(point.reach)
+                    });
+                let target_anchor = hovered
+                    .and_then(|item| ends.get(item).ok())
+                    .map_or(config.world_to_grid(world_pos), |(pos, _, _)| *pos);
+                let distance = ends
+                    .get(selected_entity)
+                    .map_or(0.0, |(pos, _, _)| tile_distance(*pos, target_anchor));
+                let out_of_reach = distance > reach;
+
+                let limited_direction = if out_of_reach {
+                    direction * (reach / distance)
                 } else {
                     direction
                 };
 
-                let end_pos =
-                    Vec2::new(selected_world_pos.x, selected_world_pos.y) + limited_direction;
-                let wire_center =
-                    (Vec2::new(selected_world_pos.x, selected_world_pos.y) + end_pos) / 2.0;
+                let end_pos = selected_world_pos + limited_direction;
+                let wire_center = (selected_world_pos + end_pos) / 2.0;
                 let length = limited_direction.length();
                 let angle = limited_direction.y.atan2(limited_direction.x);
 
                 // Create new preview with correct length
-                let preview_material =
+                let preview_color = if out_of_reach || mismatch {
+                    // Semi-transparent red
+                    Color::srgba(1.0, 0.2, 0.2, 0.5)
+                } else {
                     // Semi-transparent white
-                    materials.add(ColorMaterial::from_color(Color::srgba(1.0, 1.0, 1.0, 0.5)));
+                    Color::srgba(1.0, 1.0, 1.0, 0.5)
+                };
+                let preview_material = materials.add(ColorMaterial::from_color(preview_color));
                 // Slightly thinner than real wire
                 let preview_mesh = meshes.add(Rectangle::new(length, 1.5));
 
@@ -197,6 +536,94 @@ fn wire_preview_system(
     }
 }
 
+/// Closest wire whose segment passes within `max_distance` of `point`
+pub fn wire_near(
+    point: Vec2,
+    segments: impl Iterator<Item = (Entity, Vec2, Vec2)>,
+    max_distance: f32,
+) -> Option<Entity> {
+    segments
+        .map(|(wire_entity, from, to)| (wire_entity, distance_to_segment(point, from, to)))
+        .filter(|(_, distance)| *distance <= max_distance)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(wire_entity, _)| wire_entity)
+}
+
+fn distance_to_segment(point: Vec2, from: Vec2, to: Vec2) -> f32 {
+    let segment = to - from;
+    let length_squared = segment.length_squared();
+    if length_squared == 0.0 {
+        return point.distance(from);
+    }
+
+    let t = ((point - from).dot(segment) / length_squared).clamp(0.0, 1.0);
+    point.distance(from + segment * t)
+}
+
+fn wire_hover_system(
+    build_tool: Res<BuildTool>,
+    cursor: GridCursor,
+    wires: Query<(Entity, &Wire, &MeshMaterial2d<ColorMaterial>), With<WireVisual>>,
+    ends: WireEnds,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut hovered: Local<Option<Entity>>,
+) {
+    let cursor_world = (*build_tool == BuildTool::Wire)
+        .then(|| cursor.world_position())
+        .flatten();
+
+    let segments = wires.iter().filter_map(|(wire_entity, wire, _)| {
+        Some((
+            wire_entity,
+            wire_end(&ends, &cursor.config, wire.from, wire.from_port)?,
+            wire_end(&ends, &cursor.config, wire.to, wire.to_port)?,
+        ))
+    });
+    let now_hovered = cursor_world.and_then(|point| wire_near(point, segments, WIRE_PICK_DISTANCE));
+
+    if now_hovered == *hovered {
+        return;
+    }
+
+    for (wire_entity, is_hovered) in [(*hovered, false), (now_hovered, true)] {
+        let Some(Ok((_, wire, material_handle))) = wire_entity.map(|entity| wires.get(entity))
+        else {
+            continue;
+        };
+        if let Some(material) = materials.get_mut(&material_handle.0) {
+            material.color = if is_hovered {
+                WIRE_HOVER_COLOR
+            } else {
+                wire.kind.color()
+            };
+        }
+    }
+    *hovered = now_hovered;
+}
+
+fn wire_kind_selector(
+    keys: Res<ButtonInput<KeyCode>>,
+    build_tool: Res<BuildTool>,
+    mut wire_state: ResMut<WireState>,
+    mut text_spans: Query<&mut TextSpan>,
+    main_text_entities: Query<&Children, With<MainText>>,
+) {
+    if *build_tool != BuildTool::Wire || !keys.just_pressed(WIRE_KIND_KEY) {
+        return;
+    }
+
+    wire_state.kind = wire_state.kind.next();
+    update_tool_text(
+        &wire_tool_label(wire_state.kind),
+        &mut text_spans,
+        &main_text_entities,
+    );
+}
+
+pub fn wire_tool_label(kind: WireKind) -> String {
+    format!("Wire ({})", kind.name())
+}
+
 fn cleanup_orphaned_wires(
     mut commands: Commands,
     wires: Query<(Entity, &Wire)>,
@@ -0,0 +1,152 @@
+use crate::power::power_network::{NetworkId, PowerNetworks};
+use crate::wire_system::{ConnectionPoint, WireKind, WireState, tile_distance};
+use crate::world::edit;
+use crate::world::grid::GridPosition;
+use bevy::prelude::*;
+
+pub struct AutoWirePlugin;
+
+impl Plugin for AutoWirePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AutoWire>()
+            .add_systems(Update, auto_wire_toggle);
+    }
+}
+
+/// Toggles wiring newly placed poles to what's around them
+const AUTO_WIRE_KEY: KeyCode = KeyCode::KeyT;
+
+#[derive(Resource, Default)]
+pub struct AutoWire {
+    pub enabled: bool,
+}
+
+/// Items already connected to each other; one wire into a group is enough
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireGroup {
+    Network(NetworkId),
+    /// An item outside any network
+    Item(Entity),
+    /// A pole that is planned but not placed yet
+    Planned(usize),
+}
+
+/// Something a new pole could be wired to
+#[derive(Clone)]
+pub struct WireCandidate {
+    pub pos: GridPosition,
+    pub reach: f32,
+    /// Connections it can still take
+    pub free: usize,
+    pub group: WireGroup,
+}
+
+impl WireCandidate {
+    /// Candidate for a placed item; its group is its power network
+    pub fn of(
+        pos: GridPosition,
+        point: &ConnectionPoint,
+        networks: &PowerNetworks,
+        entity: Entity,
+    ) -> Self {
+        Self {
+            pos,
+            reach: point.reach,
+            free: (point.max_connections as usize).saturating_sub(point.connections.len()),
+            group: networks
+                .network_of(entity)
+                .map_or(WireGroup::Item(entity), WireGroup::Network),
+        }
+    }
+}
+
+/// Indices of the candidates a pole at `pos` wires itself to: the nearest one
+/// within reach of each group, until the pole runs out of connections
+pub fn choose_targets(
+    pos: GridPosition,
+    reach: f32,
+    max_connections: u8,
+    candidates: &[WireCandidate],
+) -> Vec<usize> {
+    let mut in_reach: Vec<(usize, f32)> = candidates
+        .iter()
+        .enumerate()
+        .filter(|(_, candidate)| candidate.free > 0 && candidate.pos != pos)
+        .map(|(index, candidate)| (index, tile_distance(pos, candidate.pos), candidate.reach))
+        .filter(|(_, distance, candidate_reach)| *distance <= reach.min(*candidate_reach))
+        .map(|(index, distance, _)| (index, distance))
+        .collect();
+    in_reach.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+
+    let mut groups = Vec::new();
+    let mut targets = Vec::new();
+    for (index, _) in in_reach {
+        if targets.len() >= max_connections as usize {
+            break;
+        }
+        if !groups.contains(&candidates[index].group) {
+            groups.push(candidates[index].group);
+            targets.push(index);
+        }
+    }
+    targets
+}
+
+/// Adds a planned pole to `candidates` once it's wired to `targets`, merging
+/// the groups it joins so later poles in the same plan see them as one
+pub fn join_candidates(
+    candidates: &mut Vec<WireCandidate>,
+    targets: &[usize],
+    mut pole: WireCandidate,
+) {
+    let joined: Vec<WireGroup> = targets
+        .iter()
+        .map(|index| candidates[*index].group)
+        .collect();
+    for index in targets {
+        candidates[*index].free = candidates[*index].free.saturating_sub(1);
+    }
+    for candidate in candidates.iter_mut() {
+        if joined.contains(&candidate.group) {
+            candidate.group = pole.group;
+        }
+    }
+    pole.free = pole.free.saturating_sub(targets.len());
+    candidates.push(pole);
+}
+
+/// Wires the freshly placed `pole` to the nearest items within reach,
+/// returning the wires created
+pub fn auto_wire(world: &mut World, pole: Entity) -> Vec<Entity> {
+    let Some((&pos, point)) = world
+        .get::<GridPosition>(pole)
+        .zip(world.get::<ConnectionPoint>(pole))
+    else {
+        return Vec::new();
+    };
+    let (reach, max_connections) = (point.reach, point.max_connections);
+
+    let mut points = world.query::<(Entity, &GridPosition, &ConnectionPoint)>();
+    let networks = world.resource::<PowerNetworks>();
+    let (entities, candidates): (Vec<Entity>, Vec<WireCandidate>) = points
+        .iter(world)
+        .filter(|(entity, _, _)| *entity != pole)
+        .map(|(entity, pos, point)| (entity, WireCandidate::of(*pos, point, networks, entity)))
+        .unzip();
+
+    let kind: WireKind = world.resource::<WireState>().kind;
+    choose_targets(pos, reach, max_connections, &candidates)
+        .into_iter()
+        .filter_map(|index| edit::connect(world, pole, entities[index], kind))
+        .collect()
+}
+
+fn auto_wire_toggle(keys: Res<ButtonInput<KeyCode>>, mut auto_wire: ResMut<AutoWire>) {
+    if keys.just_pressed(AUTO_WIRE_KEY) {
+        auto_wire.enabled = !auto_wire.enabled;
+        println!(
+            "Auto-wiring poles {}",
+            if auto_wire.enabled { "on" } else { "off" }
+        );
+    }
+}
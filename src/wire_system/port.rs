@@ -0,0 +1,262 @@
+use crate::wire_system::ConnectionError;
+use crate::world::grid::Footprint;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::FRAC_PI_2;
+use std::fmt;
+
+/// Which way an item points. Items are defined facing north; their ports and
+/// footprint turn with them.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Facing {
+    #[default]
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Facing {
+    /// Quarter turns clockwise from north
+    fn turns(self) -> u8 {
+        match self {
+            Facing::North => 0,
+            Facing::East => 1,
+            Facing::South => 2,
+            Facing::West => 3,
+        }
+    }
+
+    fn from_turns(turns: u8) -> Self {
+        match turns % 4 {
+            0 => Facing::North,
+            1 => Facing::East,
+            2 => Facing::South,
+            _ => Facing::West,
+        }
+    }
+
+    /// The next facing clockwise
+    pub fn next(self) -> Self {
+        Self::from_turns(self.turns() + 1)
+    }
+
+    /// This side of an item defined facing north, once the item faces `facing`
+    pub fn rotated(self, facing: Facing) -> Self {
+        Self::from_turns(self.turns() + facing.turns())
+    }
+
+    pub fn vector(self) -> Vec2 {
+        match self {
+            Facing::North => Vec2::Y,
+            Facing::East => Vec2::X,
+            Facing::South => Vec2::NEG_Y,
+            Facing::West => Vec2::NEG_X,
+        }
+    }
+
+    pub fn rotation(self) -> Quat {
+        Quat::from_rotation_z(-(self.turns() as f32) * FRAC_PI_2)
+    }
+
+    /// `footprint` as laid out on the grid when facing this way
+    pub fn rotate_footprint(self, footprint: Footprint) -> Footprint {
+        match self {
+            Facing::North | Facing::South => footprint,
+            Facing::East | Facing::West => Footprint {
+                width: footprint.height,
+                height: footprint.width,
+            },
+        }
+    }
+}
+
+impl fmt::Display for Facing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Facing::North => "North",
+            Facing::East => "East",
+            Facing::South => "South",
+            Facing::West => "West",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Which way power may pass through a port
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum PortKind {
+    Input,
+    Output,
+    #[default]
+    Any,
+}
+
+impl PortKind {
+    /// Inputs can't feed inputs and outputs can't feed outputs
+    pub fn accepts(self, other: PortKind) -> bool {
+        !matches!(
+            (self, other),
+            (PortKind::Input, PortKind::Input) | (PortKind::Output, PortKind::Output)
+        )
+    }
+}
+
+/// A port as written in an item definition, on a side of the item facing north
+#[derive(Debug, Clone, Deserialize)]
+pub struct PortDefinition {
+    pub side: Facing,
+    #[serde(default)]
+    pub kind: PortKind,
+}
+
+#[derive(Debug, Clone)]
+pub struct Port {
+    /// Side of the item on the grid, with its facing already applied
+    pub side: Facing,
+    pub kind: PortKind,
+    /// Each port takes a single wire
+    pub wire: Option<Entity>,
+}
+
+/// Ports of an item that only accepts wires on specific sides. Items without
+/// this component take any wire at their centre.
+#[derive(Component, Debug, Clone)]
+pub struct Ports {
+    ports: Vec<Port>,
+}
+
+impl Ports {
+    pub fn new(definitions: &[PortDefinition], facing: Facing) -> Self {
+        Self {
+            ports: definitions
+                .iter()
+                .map(|definition| Port {
+                    side: definition.side.rotated(facing),
+                    kind: definition.kind,
+                    wire: None,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, port: u8) -> Option<&Port> {
+        self.ports.get(port as usize)
+    }
+
+    pub fn attach(&mut self, port: u8, wire: Entity) {
+        if let Some(port) = self.ports.get_mut(port as usize) {
+            port.wire = Some(wire);
+        }
+    }
+
+    pub fn detach(&mut self, wire: Entity) {
+        for port in &mut self.ports {
+            if port.wire == Some(wire) {
+                port.wire = None;
+            }
+        }
+    }
+
+    /// Free ports, the one facing `toward` best first
+    fn free_toward(&self, toward: Vec2) -> Vec<(u8, PortKind)> {
+        let direction = toward.normalize_or_zero();
+        let mut free: Vec<(u8, &Port)> = self
+            .ports
+            .iter()
+            .enumerate()
+            .filter(|(_, port)| port.wire.is_none())
+            .map(|(index, port)| (index as u8, port))
+            .collect();
+        free.sort_by(|(_, a), (_, b)| {
+            b.side
+                .vector()
+                .dot(direction)
+                .total_cmp(&a.side.vector().dot(direction))
+        });
+        free.into_iter()
+            .map(|(index, port)| (index, port.kind))
+            .collect()
+    }
+
+    /// The free port facing `toward` best, whatever its kind
+    pub fn nearest_free(&self, toward: Vec2) -> Option<u8> {
+        self.free_toward(toward).first().map(|(index, _)| *index)
+    }
+}
+
+/// Kind of the port a wire end attaches to; portless items count as `Any`
+pub fn port_kind(ports: Option<&Ports>, port: Option<u8>) -> PortKind {
+    ports
+        .zip(port)
+        .and_then(|(ports, port)| ports.get(port))
+        .map_or(PortKind::Any, |port| port.kind)
+}
+
+/// Whether a wire can attach to `from_port` and `to_port`: each must be a free
+/// port of its item (or `None` on a portless item) and their kinds must fit
+pub fn ports_fit(
+    from: Option<&Ports>,
+    from_port: Option<u8>,
+    to: Option<&Ports>,
+    to_port: Option<u8>,
+) -> bool {
+    let free = |ports: Option<&Ports>, port: Option<u8>| match (ports, port) {
+        (Some(ports), Some(port)) => ports.get(port).is_some_and(|port| port.wire.is_none()),
+        (None, None) => true,
+        _ => false,
+    };
+    free(from, from_port)
+        && free(to, to_port)
+        && port_kind(from, from_port).accepts(port_kind(to, to_port))
+}
+
+/// Offset of `port` from the centre of an item covering `footprint`: the middle
+/// of the footprint edge on the port's side
+pub fn port_offset(
+    ports: Option<&Ports>,
+    port: Option<u8>,
+    footprint: Footprint,
+    tile_size: f32,
+) -> Vec2 {
+    let Some(port) = ports.zip(port).and_then(|(ports, port)| ports.get(port)) else {
+        return Vec2::ZERO;
+    };
+    let half_extent = Vec2::new(footprint.width as f32, footprint.height as f32) * tile_size / 2.0;
+    port.side.vector() * half_extent
+}
+
+/// Picks the ports a wire between two items attaches to: on each end the free
+/// port facing the other end best whose kind fits the other side. Portless
+/// ends attach at their centre (`None`).
+pub fn pick_ports(
+    from: Option<&Ports>,
+    from_center: Vec2,
+    to: Option<&Ports>,
+    to_center: Vec2,
+) -> Result<(Option<u8>, Option<u8>), ConnectionError> {
+    let candidates = |ports: Option<&Ports>, toward: Vec2| match ports {
+        Some(ports) => ports
+            .free_toward(toward)
+            .into_iter()
+            .map(|(index, kind)| (Some(index), kind))
+            .collect(),
+        None => vec![(None, PortKind::Any)],
+    };
+    let from_candidates: Vec<(Option<u8>, PortKind)> = candidates(from, to_center - from_center);
+    let to_candidates: Vec<(Option<u8>, PortKind)> = candidates(to, from_center - to_center);
+
+    if from_candidates.is_empty() || to_candidates.is_empty() {
+        return Err(ConnectionError::NoFreePort);
+    }
+
+    from_candidates
+        .iter()
+        .find_map(|(from_port, from_kind)| {
+            to_candidates
+                .iter()
+                .find(|(_, to_kind)| from_kind.accepts(*to_kind))
+                .map(|(to_port, _)| (*from_port, *to_port))
+        })
+        .ok_or(ConnectionError::PortMismatch)
+}
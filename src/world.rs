@@ -1,2 +1,7 @@
 pub mod camera;
+pub mod chunk;
+pub mod clock;
+pub mod edit;
 pub mod grid;
+pub mod history;
+pub mod tile_index;
@@ -3,6 +3,16 @@ use bevy::prelude::*;
 
 pub struct CameraPlugin;
 
+/// World units per second the camera pans at zoom 1; zoomed out it pans faster
+const PAN_SPEED: f32 = 400.0;
+/// Arrow keys pan; S is taken by the select tool
+const PAN_KEYS: [(KeyCode, Vec2); 4] = [
+    (KeyCode::ArrowUp, Vec2::Y),
+    (KeyCode::ArrowDown, Vec2::NEG_Y),
+    (KeyCode::ArrowLeft, Vec2::NEG_X),
+    (KeyCode::ArrowRight, Vec2::X),
+];
+
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, setup)
@@ -21,12 +31,26 @@ fn controls(
     window: Single<&Window>,
     input: Res<ButtonInput<KeyCode>>,
     mut mouse_wheel_events: EventReader<MouseWheel>,
-    time: Res<Time<Fixed>>,
+    time: Res<Time>,
 ) {
     let Ok((_, mut transform, mut projection)) = camera_query.single_mut() else {
         return;
     };
 
+    let direction: Vec2 = PAN_KEYS
+        .iter()
+        .filter(|(key, _)| input.pressed(*key))
+        .map(|(_, direction)| *direction)
+        .sum();
+    if direction != Vec2::ZERO {
+        let scale = match &*projection {
+            Projection::Orthographic(projection_2d) => projection_2d.scale,
+            _ => 1.0,
+        };
+        let step = direction.normalize() * PAN_SPEED * scale * time.delta_secs();
+        transform.translation += step.extend(0.0);
+    }
+
     for mouse_wheel_event in mouse_wheel_events.read() {
         if let Projection::Orthographic(projection_2d) = &mut *projection {
             if mouse_wheel_event.y < 0.0 {
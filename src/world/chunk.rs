@@ -0,0 +1,341 @@
+use crate::world::grid::{GridConfig, GridPosition};
+use crate::world::tile_index::TileIndex;
+use bevy::asset::RenderAssetUsages;
+use bevy::color::palettes::tailwind::NEUTRAL_700;
+use bevy::ecs::system::SystemState;
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+
+pub struct ChunkPlugin;
+
+impl Plugin for ChunkPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (load_visible_chunks, unload_hidden_chunks, update_tile_tints),
+        );
+    }
+}
+
+/// Tiles along each side of a chunk
+pub const CHUNK_SIZE: i32 = 16;
+/// Chunks kept loaded past the edge of the view so panning doesn't reveal gaps
+const LOAD_MARGIN: i32 = 1;
+/// Empty chunks are only dropped once they're this far out, so hovering at
+/// the edge doesn't load and unload the same chunk every frame
+const UNLOAD_MARGIN: i32 = 3;
+/// Keeps a big zoom out from spawning hundreds of chunks in a single frame
+const MAX_CHUNKS_PER_FRAME: usize = 4;
+const TILE_COLOR: Color = Color::Srgba(NEUTRAL_700);
+
+/// Chunks further than this from the view centre aren't loaded, however far
+/// the camera is zoomed out
+const MAX_LOAD_RADIUS: i32 = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ChunkPosition {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl ChunkPosition {
+    /// The chunk containing the tile at `pos`
+    pub fn of(pos: GridPosition) -> Self {
+        Self {
+            x: pos.x.div_euclid(CHUNK_SIZE),
+            y: pos.y.div_euclid(CHUNK_SIZE),
+        }
+    }
+
+    /// Whether any of the chunk's tiles lie inside the grid bounds
+    pub fn in_bounds(self, config: &GridConfig) -> bool {
+        let (min_x, min_y) = (self.x * CHUNK_SIZE, self.y * CHUNK_SIZE);
+        let (max_x, max_y) = (min_x + CHUNK_SIZE - 1, min_y + CHUNK_SIZE - 1);
+        config.bounds.is_none_or(|bounds| {
+            min_x <= bounds.max.x
+                && max_x >= bounds.min.x
+                && min_y <= bounds.max.y
+                && max_y >= bounds.min.y
+        })
+    }
+
+    pub fn tiles(self) -> impl Iterator<Item = GridPosition> {
+        let (origin_x, origin_y) = (self.x * CHUNK_SIZE, self.y * CHUNK_SIZE);
+        (0..CHUNK_SIZE).flat_map(move |dx| {
+            (0..CHUNK_SIZE).map(move |dy| GridPosition {
+                x: origin_x + dx,
+                y: origin_y + dy,
+            })
+        })
+    }
+}
+
+/// One loaded chunk, drawn as a single mesh; despawning it unloads the chunk
+#[derive(Component)]
+pub struct Chunk(pub ChunkPosition);
+
+/// Per-tile fill colours of a chunk, `None` meaning the default tile colour.
+/// Changing them recolours the chunk mesh.
+#[derive(Component)]
+pub struct TileTints {
+    tints: Vec<Option<Color>>,
+}
+
+impl TileTints {
+    fn new() -> Self {
+        Self {
+            tints: vec![None; (CHUNK_SIZE * CHUNK_SIZE) as usize],
+        }
+    }
+
+    pub fn get(&self, pos: GridPosition) -> Option<Color> {
+        self.tints[tile_slot(pos)]
+    }
+
+    pub fn set(&mut self, pos: GridPosition, tint: Option<Color>) {
+        self.tints[tile_slot(pos)] = tint;
+    }
+}
+
+/// Position of a tile within its chunk, in `ChunkPosition::tiles` order
+fn tile_slot(pos: GridPosition) -> usize {
+    (pos.x.rem_euclid(CHUNK_SIZE) * CHUNK_SIZE + pos.y.rem_euclid(CHUNK_SIZE)) as usize
+}
+
+/// Tints the tile at `pos`, leaving untouched chunks unchanged so they aren't
+/// rebuilt. Does nothing if the chunk isn't loaded.
+pub fn tint_tile(
+    index: &TileIndex,
+    tints: &mut Query<&mut TileTints>,
+    pos: GridPosition,
+    tint: Option<Color>,
+) {
+    let Some(Ok(mut chunk_tints)) = index
+        .chunk(ChunkPosition::of(pos))
+        .map(|chunk| tints.get_mut(chunk))
+    else {
+        return;
+    };
+    if chunk_tints.get(pos) != tint {
+        chunk_tints.set(pos, tint);
+    }
+}
+
+/// One mesh for the whole chunk: a backing quad in the line colour with a
+/// slightly smaller quad per tile on top, so the gaps form the grid lines.
+/// Tiles outside the grid bounds keep their vertices but collapse to a point.
+fn chunk_mesh(chunk: ChunkPosition, tints: &TileTints, config: &GridConfig) -> Mesh {
+    let half_tile = config.tile_size / 2.0;
+    let in_bounds: Vec<GridPosition> = chunk.tiles().filter(|pos| config.contains(*pos)).collect();
+    let corner = |pick: fn(i32, i32) -> i32| GridPosition {
+        x: in_bounds
+            .iter()
+            .map(|pos| pos.x)
+            .reduce(pick)
+            .unwrap_or_default(),
+        y: in_bounds
+            .iter()
+            .map(|pos| pos.y)
+            .reduce(pick)
+            .unwrap_or_default(),
+    };
+    let backing_min = config.grid_to_world(corner(i32::min)).truncate() - Vec2::splat(half_tile);
+    let backing_max = config.grid_to_world(corner(i32::max)).truncate() + Vec2::splat(half_tile);
+
+    let mut positions = Vec::new();
+    let mut indices = Vec::new();
+    let mut push_quad = |min: Vec2, max: Vec2, z: f32| {
+        let start = positions.len() as u32;
+        positions.extend([
+            [min.x, min.y, z],
+            [max.x, min.y, z],
+            [max.x, max.y, z],
+            [min.x, max.y, z],
+        ]);
+        indices.extend([start, start + 1, start + 2, start, start + 2, start + 3]);
+    };
+
+    if in_bounds.is_empty() {
+        push_quad(Vec2::ZERO, Vec2::ZERO, -0.1);
+    } else {
+        push_quad(backing_min, backing_max, -0.1);
+    }
+    for pos in chunk.tiles() {
+        let center = config.grid_to_world(pos).truncate();
+        let half_cell = if config.contains(pos) {
+            half_tile - 0.5
+        } else {
+            0.0
+        };
+        push_quad(
+            center - Vec2::splat(half_cell),
+            center + Vec2::splat(half_cell),
+            0.0,
+        );
+    }
+
+    let vertex_count = positions.len();
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0]; vertex_count])
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; vertex_count])
+    .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, chunk_colors(tints, config))
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+fn chunk_colors(tints: &TileTints, config: &GridConfig) -> Vec<[f32; 4]> {
+    let quad = |color: Color| [color.to_linear().to_f32_array(); 4];
+
+    // The backing quad shows through the gaps between tiles as the grid lines
+    let mut colors = quad(config.line_color).to_vec();
+    for tint in &tints.tints {
+        colors.extend(quad(tint.unwrap_or(TILE_COLOR)));
+    }
+    colors
+}
+
+pub fn spawn_chunk(
+    commands: &mut Commands,
+    chunk: ChunkPosition,
+    config: &GridConfig,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+) -> Entity {
+    let tints = TileTints::new();
+    let mesh = meshes.add(chunk_mesh(chunk, &tints, config));
+
+    commands
+        .spawn((
+            Chunk(chunk),
+            Name::new(format!("Chunk ({}, {})", chunk.x, chunk.y)),
+            Mesh2d(mesh),
+            // White so the vertex colours come through unchanged
+            MeshMaterial2d(materials.add(ColorMaterial::default())),
+            Transform::default(),
+            tints,
+        ))
+        .id()
+}
+
+/// Loads the chunk immediately if it isn't already, e.g. when a save places
+/// an item somewhere the camera hasn't been yet
+#[allow(clippy::type_complexity)]
+pub fn ensure_chunk(world: &mut World, chunk: ChunkPosition) -> Entity {
+    if let Some(entity) = world.resource::<TileIndex>().chunk(chunk) {
+        return entity;
+    }
+
+    let mut state: SystemState<(
+        Commands,
+        Res<GridConfig>,
+        ResMut<Assets<Mesh>>,
+        ResMut<Assets<ColorMaterial>>,
+    )> = SystemState::new(world);
+    let (mut commands, config, mut meshes, mut materials) = state.get_mut(world);
+    let entity = spawn_chunk(&mut commands, chunk, &config, &mut meshes, &mut materials);
+    state.apply(world);
+
+    entity
+}
+
+/// Chunk range covered by the camera, clamped to `MAX_LOAD_RADIUS`
+fn visible_chunks(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    config: &GridConfig,
+) -> Option<(ChunkPosition, ChunkPosition)> {
+    let viewport = camera.logical_viewport_rect()?;
+    let a = camera
+        .viewport_to_world_2d(camera_transform, viewport.min)
+        .ok()?;
+    let b = camera
+        .viewport_to_world_2d(camera_transform, viewport.max)
+        .ok()?;
+
+    let chunk_at = |point: Vec2| ChunkPosition::of(config.world_to_grid(point));
+    let (min, max) = (chunk_at(a.min(b)), chunk_at(a.max(b)));
+    let center = chunk_at((a + b) / 2.0);
+
+    Some((
+        ChunkPosition {
+            x: min.x.max(center.x - MAX_LOAD_RADIUS),
+            y: min.y.max(center.y - MAX_LOAD_RADIUS),
+        },
+        ChunkPosition {
+            x: max.x.min(center.x + MAX_LOAD_RADIUS),
+            y: max.y.min(center.y + MAX_LOAD_RADIUS),
+        },
+    ))
+}
+
+fn load_visible_chunks(
+    mut commands: Commands,
+    camera: Query<(&Camera, &GlobalTransform)>,
+    config: Res<GridConfig>,
+    index: Res<TileIndex>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let Ok((camera, camera_transform)) = camera.single() else {
+        return;
+    };
+    let Some((min, max)) = visible_chunks(camera, camera_transform, &config) else {
+        return;
+    };
+
+    let mut missing: Vec<ChunkPosition> = (min.x - LOAD_MARGIN..=max.x + LOAD_MARGIN)
+        .flat_map(|x| {
+            (min.y - LOAD_MARGIN..=max.y + LOAD_MARGIN).map(move |y| ChunkPosition { x, y })
+        })
+        .filter(|chunk| index.chunk(*chunk).is_none() && chunk.in_bounds(&config))
+        .collect();
+
+    // Fill in from the middle of the view outwards
+    let (center_x, center_y) = ((min.x + max.x) / 2, (min.y + max.y) / 2);
+    missing.sort_by_key(|chunk| (chunk.x - center_x).abs() + (chunk.y - center_y).abs());
+
+    for chunk in missing.into_iter().take(MAX_CHUNKS_PER_FRAME) {
+        spawn_chunk(&mut commands, chunk, &config, &mut meshes, &mut materials);
+    }
+}
+
+fn unload_hidden_chunks(
+    mut commands: Commands,
+    camera: Query<(&Camera, &GlobalTransform)>,
+    config: Res<GridConfig>,
+    index: Res<TileIndex>,
+) {
+    let Ok((camera, camera_transform)) = camera.single() else {
+        return;
+    };
+    let Some((min, max)) = visible_chunks(camera, camera_transform, &config) else {
+        return;
+    };
+
+    for (chunk, entity) in index.chunks() {
+        let outside = chunk.x < min.x - UNLOAD_MARGIN
+            || chunk.x > max.x + UNLOAD_MARGIN
+            || chunk.y < min.y - UNLOAD_MARGIN
+            || chunk.y > max.y + UNLOAD_MARGIN;
+
+        // Chunks with items on them stay loaded
+        if outside && index.occupied_in_chunk(chunk) == 0 {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn update_tile_tints(
+    chunks: Query<(&TileTints, &Mesh2d), Changed<TileTints>>,
+    config: Res<GridConfig>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    for (tints, mesh) in chunks.iter() {
+        if let Some(mesh) = meshes.get_mut(&mesh.0) {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, chunk_colors(tints, &config));
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::TAU;
+
+pub struct ClockPlugin;
+
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WorldClock>()
+            .add_systems(Startup, setup_ambient_overlay)
+            .add_systems(
+                Update,
+                (advance_clock, day_length_input, update_ambient_overlay),
+            );
+    }
+}
+
+/// Halve or double the length of a day
+const SHORTER_DAY_KEY: KeyCode = KeyCode::BracketLeft;
+const LONGER_DAY_KEY: KeyCode = KeyCode::BracketRight;
+const MIN_DAY_LENGTH: f32 = 15.0;
+const MAX_DAY_LENGTH: f32 = 3840.0;
+
+/// Sun height over which dusk fades into full daylight
+const TWILIGHT: f32 = 0.25;
+/// Opacity of the ambient overlay at midnight
+const MAX_DARKNESS: f32 = 0.7;
+/// Above placed items and previews, below light glows
+const OVERLAY_Z: f32 = 10.0;
+/// Large enough to cover the view at any sensible zoom
+const OVERLAY_SIZE: f32 = 1.0e6;
+
+/// In-game time driving the day/night cycle
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct WorldClock {
+    /// Seconds since the world started
+    pub elapsed: f32,
+    /// Seconds in one full day
+    pub day_length: f32,
+}
+
+impl Default for WorldClock {
+    fn default() -> Self {
+        Self {
+            // Start in the morning so the sun is coming up
+            elapsed: 30.0,
+            day_length: 120.0,
+        }
+    }
+}
+
+impl WorldClock {
+    /// Fraction of the day gone by: 0 is midnight, 0.5 is noon
+    pub fn time_of_day(&self) -> f32 {
+        (self.elapsed / self.day_length).rem_euclid(1.0)
+    }
+
+    /// Height of the sun from 0 (below the horizon) to 1 (noon)
+    pub fn daylight(&self) -> f32 {
+        (-(self.time_of_day() * TAU).cos()).max(0.0)
+    }
+
+    /// 0 in full daylight, 1 once the sun is down
+    pub fn darkness(&self) -> f32 {
+        1.0 - (self.daylight() / TWILIGHT).min(1.0)
+    }
+
+    /// Dark enough for lights to come on
+    pub fn is_dark(&self) -> bool {
+        self.darkness() > 0.5
+    }
+
+    /// Changes the day length without jumping to another time of day
+    pub fn set_day_length(&mut self, day_length: f32) {
+        let time_of_day = self.time_of_day();
+        let days = (self.elapsed / self.day_length).floor();
+        self.day_length = day_length;
+        self.elapsed = (days + time_of_day) * day_length;
+    }
+}
+
+fn advance_clock(time: Res<Time>, mut clock: ResMut<WorldClock>) {
+    clock.elapsed += time.delta_secs();
+}
+
+fn day_length_input(keys: Res<ButtonInput<KeyCode>>, mut clock: ResMut<WorldClock>) {
+    let day_length = if keys.just_pressed(SHORTER_DAY_KEY) {
+        clock.day_length / 2.0
+    } else if keys.just_pressed(LONGER_DAY_KEY) {
+        clock.day_length * 2.0
+    } else {
+        return;
+    };
+
+    clock.set_day_length(day_length.clamp(MIN_DAY_LENGTH, MAX_DAY_LENGTH));
+    println!("Day length: {}s", clock.day_length);
+}
+
+/// Darkens everything under it as night falls
+#[derive(Component)]
+struct AmbientOverlay;
+
+fn setup_ambient_overlay(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.spawn((
+        AmbientOverlay,
+        Mesh2d(meshes.add(Rectangle::new(OVERLAY_SIZE, OVERLAY_SIZE))),
+        MeshMaterial2d(materials.add(ColorMaterial::from_color(Color::NONE))),
+        Transform::from_translation(Vec3::Z * OVERLAY_Z),
+    ));
+}
+
+fn update_ambient_overlay(
+    clock: Res<WorldClock>,
+    camera: Query<&Transform, (With<Camera2d>, Without<AmbientOverlay>)>,
+    mut overlay: Query<(&mut Transform, &MeshMaterial2d<ColorMaterial>), With<AmbientOverlay>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let Ok((mut transform, material_handle)) = overlay.single_mut() else {
+        return;
+    };
+    // Keep the overlay centred on the view
+    if let Ok(camera_transform) = camera.single() {
+        transform.translation.x = camera_transform.translation.x;
+        transform.translation.y = camera_transform.translation.y;
+    }
+
+    let color = Color::srgba(0.02, 0.02, 0.08, MAX_DARKNESS * clock.darkness());
+    // Looked up read-only first; a mutable lookup re-uploads the material
+    let changed = materials
+        .get(&material_handle.0)
+        .is_some_and(|material| material.color != color);
+    if let Some(material) = changed
+        .then(|| materials.get_mut(&material_handle.0))
+        .flatten()
+    {
+        material.color = color;
+    }
+}
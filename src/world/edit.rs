@@ -0,0 +1,213 @@
+use crate::items::definition::ItemId;
+use crate::items::registry::{Item, ItemRegistry, spawn_item};
+use crate::wire_system::port::{Facing, ports_fit};
+use crate::wire_system::{
+    ConnectionPoint, Wire, WireEnds, WireIndex, WireKind, WireState, check_connection,
+};
+use crate::world::chunk::{ChunkPosition, ensure_chunk};
+use crate::world::grid::{Footprint, GridConfig, GridPosition};
+use crate::world::tile_index::TileIndex;
+use bevy::ecs::system::SystemState;
+use bevy::prelude::*;
+
+// World-level editing operations addressed by grid position. Used wherever the
+// world is changed from data rather than from a click, e.g. loading a save or
+// replaying the edit history.
+
+pub fn item_at(world: &World, pos: GridPosition) -> Option<Entity> {
+    world.resource::<TileIndex>().item(pos)
+}
+
+/// Whether a footprint anchored at `pos` would leave the grid or overlap an
+/// item other than those in `ignore`
+pub fn footprint_blocked(
+    world: &World,
+    pos: GridPosition,
+    footprint: Footprint,
+    ignore: &[Entity],
+) -> bool {
+    world.resource::<TileIndex>().footprint_blocked(
+        world.resource::<GridConfig>(),
+        pos,
+        footprint,
+        ignore,
+    )
+}
+
+/// Spawns the item `id` facing `facing` with its footprint anchored at `pos`,
+/// loading the chunks it covers first, if every covered tile is empty
+#[allow(clippy::type_complexity)]
+pub fn place_item(
+    world: &mut World,
+    id: &ItemId,
+    pos: GridPosition,
+    facing: Facing,
+) -> Option<Entity> {
+    let Some(footprint) = world
+        .resource::<ItemRegistry>()
+        .get(id)
+        .map(|definition| facing.rotate_footprint(definition.footprint))
+    else {
+        println!("Cannot place {}: no item definition", id);
+        return None;
+    };
+    let config = world.resource::<GridConfig>();
+    if !footprint.tiles(pos).all(|tile| config.contains(tile)) {
+        return None;
+    }
+    for tile in footprint.tiles(pos) {
+        ensure_chunk(world, ChunkPosition::of(tile));
+    }
+    if footprint_blocked(world, pos, footprint, &[]) {
+        return None;
+    }
+
+    let mut state: SystemState<(
+        Commands,
+        Res<ItemRegistry>,
+        Res<GridConfig>,
+        ResMut<Assets<Mesh>>,
+        ResMut<Assets<ColorMaterial>>,
+    )> = SystemState::new(world);
+    let (mut commands, registry, config, mut meshes, mut materials) = state.get_mut(world);
+
+    let definition = registry.get(id)?;
+    let item = spawn_item(
+        &mut commands,
+        definition,
+        pos,
+        facing,
+        &config,
+        &mut meshes,
+        &mut materials,
+    );
+    state.apply(world);
+
+    Some(item)
+}
+
+/// Wires `from` to `to` immediately, returning the new wire
+pub fn connect(world: &mut World, from: Entity, to: Entity, kind: WireKind) -> Option<Entity> {
+    connect_at(world, from, to, kind, None)
+}
+
+/// Like `connect`, but attaches to the given `(from, to)` ports when they're
+/// still free and fit, so restored wires keep their sides and direction
+#[allow(clippy::type_complexity)]
+pub fn connect_at(
+    world: &mut World,
+    from: Entity,
+    to: Entity,
+    kind: WireKind,
+    ports: Option<(Option<u8>, Option<u8>)>,
+) -> Option<Entity> {
+    if from == to {
+        return None;
+    }
+
+    let mut state: SystemState<(
+        Query<&mut ConnectionPoint>,
+        Res<WireIndex>,
+        WireEnds,
+        Res<GridConfig>,
+    )> = SystemState::new(world);
+    let (connection_points, wire_index, ends, config) = state.get_mut(world);
+    let (from_port, to_port) =
+        match check_connection(from, to, &connection_points, &wire_index, &ends, &config) {
+            Ok(picked) => ports
+                .filter(|(from_port, to_port)| {
+                    let item_ports = |entity| ends.get(entity).ok().and_then(|(_, _, ports)| ports);
+                    ports_fit(item_ports(from), *from_port, item_ports(to), *to_port)
+                })
+                .unwrap_or(picked),
+            Err(reason) => {
+                let position = |entity| ends.get(entity).ok().map(|(pos, _, _)| *pos);
+                println!(
+                    "Cannot connect {:?} to {:?}: {}",
+                    position(from),
+                    position(to),
+                    reason
+                );
+                return None;
+            }
+        };
+
+    let wire = world
+        .spawn((
+            Wire {
+                from,
+                to,
+                kind,
+                from_port,
+                to_port,
+            },
+            Name::new("Wire"),
+        ))
+        .id();
+    for endpoint in [from, to] {
+        if let Some(mut connection_point) = world.get_mut::<ConnectionPoint>(endpoint) {
+            connection_point.add_connection(wire);
+        }
+    }
+
+    Some(wire)
+}
+
+/// Removes every placed item and wire and empties all tiles
+pub fn clear_all(world: &mut World) {
+    let doomed: Vec<Entity> = world
+        .query_filtered::<Entity, Or<(With<Item>, With<Wire>)>>()
+        .iter(world)
+        .collect();
+    for entity in doomed {
+        world.despawn(entity);
+    }
+
+    if let Some(mut wire_state) = world.get_resource_mut::<WireState>() {
+        wire_state.selected_connection = None;
+        wire_state.selected_position = None;
+    }
+}
+
+/// Wires attached to `entity`, in either direction
+pub fn wires_of(world: &World, entity: Entity) -> Vec<Entity> {
+    let mut wires: Vec<Entity> = world
+        .get::<ConnectionPoint>(entity)
+        .map(|point| point.connections.iter().copied().collect())
+        .unwrap_or_default();
+    // Sorted so undo restores wires in a stable order
+    wires.sort();
+    wires
+}
+
+pub fn wire_between(world: &World, a: Entity, b: Entity) -> Option<Entity> {
+    world.resource::<WireIndex>().get(a, b)
+}
+
+/// Detaches a wire from both endpoints and despawns it
+pub fn remove_wire(world: &mut World, wire_entity: Entity) -> bool {
+    let Some(wire) = world.get::<Wire>(wire_entity) else {
+        return false;
+    };
+    let endpoints = [wire.from, wire.to];
+
+    for endpoint in endpoints {
+        if let Some(mut connection_point) = world.get_mut::<ConnectionPoint>(endpoint) {
+            connection_point.remove_connection(wire_entity);
+        }
+    }
+    world.despawn(wire_entity)
+}
+
+/// Removes the item covering `pos` together with its wires and empties every
+/// tile of its footprint
+pub fn remove_item(world: &mut World, pos: GridPosition) -> bool {
+    let Some(item) = item_at(world, pos) else {
+        return false;
+    };
+    for wire_entity in wires_of(world, item) {
+        remove_wire(world, wire_entity);
+    }
+    // The tile index forgets the footprint when the item goes
+    world.despawn(item)
+}
@@ -1,46 +1,83 @@
-use crate::items::{generator, light, power_pole};
-use crate::tools::build_tool::{BuildTool, TileContent};
-use crate::wire_system::{ConnectionPoint, Wire, WireState};
+use crate::items::power_pole;
+use crate::items::registry::ItemRegistry;
+use crate::save::{ItemSnapshot, WireSnapshot};
+use crate::tools::build_tool::BuildTool;
+use crate::wire_system::port::Facing;
+use crate::wire_system::{
+    ConnectionError, ConnectionPoint, WIRE_PICK_DISTANCE, Wire, WireEnds, WireIndex, WireState,
+    check_connection, tile_distance, wire_end, wire_near,
+};
+use crate::world::chunk::{ChunkPlugin, TileTints, tint_tile};
+use crate::world::edit;
+use crate::world::history::{self, Edit, EditHistory};
+use crate::world::tile_index::{TileIndex, TileIndexPlugin};
 use bevy::app::{App, Startup};
 use bevy::asset::Assets;
 use bevy::color::palettes::basic::{BLACK, WHITE};
-use bevy::color::palettes::tailwind::NEUTRAL_700;
+use bevy::color::palettes::tailwind::NEUTRAL_600;
+use bevy::ecs::system::SystemParam;
 use bevy::math::Vec3;
 use bevy::prelude::*;
 use bevy::sprite::MeshMaterial2d;
+use serde::{Deserialize, Serialize};
 
 pub struct GridPlugin;
 
 impl Plugin for GridPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<HoverState>()
+        app.init_resource::<GridConfig>()
+            .init_resource::<HoverState>()
             .init_resource::<WireState>()
-            .add_systems(Startup, (setup, setup_hover_borders))
-            .add_systems(
-                Update,
-                (
-                    click_place_system,
-                    hover_mouse,
-                    process_pending_wire_connections,
-                ),
-            );
+            .add_plugins((ChunkPlugin, TileIndexPlugin))
+            .add_systems(Startup, setup_hover_borders)
+            .add_systems(Update, (click_place_system, hover_mouse));
     }
 }
 
-#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct GridPosition {
     pub(crate) x: i32,
     pub(crate) y: i32,
 }
 
-#[derive(Component)]
-pub struct Material2dHandle(pub(crate) Handle<ColorMaterial>);
+impl GridPosition {
+    pub fn offset(self, by: IVec2) -> Self {
+        Self {
+            x: self.x + by.x,
+            y: self.y + by.y,
+        }
+    }
+}
 
-#[derive(Component)]
-struct Hoverable;
+/// Tiles an item covers, extending right and up from its `GridPosition`
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct Footprint {
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Default for Footprint {
+    fn default() -> Self {
+        Self {
+            width: 1,
+            height: 1,
+        }
+    }
+}
+
+impl Footprint {
+    pub fn tiles(self, anchor: GridPosition) -> impl Iterator<Item = GridPosition> {
+        (0..self.width.max(1)).flat_map(move |dx| {
+            (0..self.height.max(1)).map(move |dy| GridPosition {
+                x: anchor.x + dx,
+                y: anchor.y + dy,
+            })
+        })
+    }
+}
 
 #[derive(Component)]
-struct Placed; // Marker for something placed on a tile
+pub struct Material2dHandle(pub(crate) Handle<ColorMaterial>);
 
 #[derive(Component)]
 struct HoverBorder; // Marker for hover border entities
@@ -58,95 +95,91 @@ struct HoverState {
     last_hovered: Option<GridPosition>,
 }
 
-#[derive(Component)]
-struct PendingWireConnection {
-    from: Entity,
-    to: Entity,
+/// Fill of the hovered tile, drawn under the hover border
+const HOVER_TINT: Color = Color::Srgba(NEUTRAL_600);
+
+/// Layout of the grid, read by everything that converts between grid and world
+/// coordinates. Insert one before `GridPlugin` to run at a different size.
+#[derive(Resource, Clone)]
+pub struct GridConfig {
+    /// Side of a tile in world units
+    pub tile_size: f32,
+    /// Inclusive range of tiles that exist, or `None` for an unbounded grid
+    pub bounds: Option<IRect>,
+    /// World position of the centre of tile (0, 0)
+    pub origin: Vec2,
+    pub line_color: Color,
 }
 
-#[derive(Component)]
-struct Tile {
-    content: Option<Entity>, // child or placed thing
+impl Default for GridConfig {
+    fn default() -> Self {
+        Self {
+            tile_size: 16.0,
+            bounds: None,
+            origin: Vec2::ZERO,
+            line_color: Color::Srgba(BLACK),
+        }
+    }
 }
 
-const TILE_SIZE: i32 = 16;
-const GRID_SIZE: i32 = 32;
-const SPACING: i32 = 2;
+impl GridConfig {
+    pub fn grid_to_world(&self, pos: GridPosition) -> Vec3 {
+        (self.origin + Vec2::new(pos.x as f32, pos.y as f32) * self.tile_size).extend(0.0)
+    }
+
+    /// Centre of `footprint` placed at `anchor`, where the item is drawn
+    pub fn footprint_center(&self, anchor: GridPosition, footprint: Footprint) -> Vec3 {
+        let offset = Vec2::new(
+            (footprint.width.max(1) - 1) as f32,
+            (footprint.height.max(1) - 1) as f32,
+        ) * self.tile_size
+            / 2.0;
+        self.grid_to_world(anchor) + offset.extend(0.0)
+    }
+
+    /// The tile whose square contains `point`
+    pub fn world_to_grid(&self, point: Vec2) -> GridPosition {
+        let tile = ((point - self.origin) / self.tile_size).round();
+        GridPosition {
+            x: tile.x as i32,
+            y: tile.y as i32,
+        }
+    }
 
-pub fn grid_to_world(pos: GridPosition) -> Vec3 {
-    Vec3::new((pos.x * TILE_SIZE) as f32, (pos.y * TILE_SIZE) as f32, 0.0)
+    pub fn contains(&self, pos: GridPosition) -> bool {
+        self.bounds
+            .is_none_or(|bounds| bounds.contains(IVec2::new(pos.x, pos.y)))
+    }
 }
 
-fn setup(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
-    let tiles_per_axis = GRID_SIZE / SPACING;
-    let tile_half = tiles_per_axis / 2;
-
-    for x in 0..GRID_SIZE {
-        for y in 0..GRID_SIZE {
-            let position = GridPosition {
-                x: x - (tile_half * 2),
-                y: y - (tile_half * 2),
-            };
+/// The cursor in world and grid coordinates, for systems acting on whatever is
+/// under the mouse
+#[derive(SystemParam)]
+pub struct GridCursor<'w, 's> {
+    windows: Query<'w, 's, &'static Window>,
+    camera: Query<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+    pub config: Res<'w, GridConfig>,
+}
 
-            let cell_material_handle = materials.add(ColorMaterial::from_color(NEUTRAL_700));
-            let cell_mesh_handle = meshes.add(Rectangle::new(
-                TILE_SIZE as f32 - 1.0,
-                TILE_SIZE as f32 - 1.0,
-            ));
-
-            // Spawn tile
-            commands.spawn((
-                Mesh2d(cell_mesh_handle.clone()),
-                Material2dHandle(cell_material_handle.clone()),
-                MeshMaterial2d(cell_material_handle),
-                Transform::from_translation(grid_to_world(position)),
-                Hoverable,
-                position,
-                Tile { content: None },
-            ));
-
-            let border_material_handle = materials.add(ColorMaterial::from_color(BLACK));
-            let tile_pos = grid_to_world(position);
-            let half_tile = TILE_SIZE as f32 / 2.0;
-
-            // Create 4 border lines (top, bottom, left, right)
-            // Top border
-            commands.spawn((
-                Mesh2d(meshes.add(Rectangle::new(TILE_SIZE as f32, 1.0))),
-                MeshMaterial2d(border_material_handle.clone()),
-                Transform::from_translation(tile_pos + Vec3::new(0.0, half_tile, 0.1)),
-            ));
-
-            // Bottom border
-            commands.spawn((
-                Mesh2d(meshes.add(Rectangle::new(TILE_SIZE as f32, 1.0))),
-                MeshMaterial2d(border_material_handle.clone()),
-                Transform::from_translation(tile_pos + Vec3::new(0.0, -half_tile, 0.1)),
-            ));
-
-            // Left border
-            commands.spawn((
-                Mesh2d(meshes.add(Rectangle::new(1.0, TILE_SIZE as f32))),
-                MeshMaterial2d(border_material_handle.clone()),
-                Transform::from_translation(tile_pos + Vec3::new(-half_tile, 0.0, 0.1)),
-            ));
-
-            // Right border
-            commands.spawn((
-                Mesh2d(meshes.add(Rectangle::new(1.0, TILE_SIZE as f32))),
-                MeshMaterial2d(border_material_handle),
-                Transform::from_translation(tile_pos + Vec3::new(half_tile, 0.0, 0.1)),
-            ));
-        }
+impl GridCursor<'_, '_> {
+    /// `None` while the cursor is outside the window
+    pub fn world_position(&self) -> Option<Vec2> {
+        let (camera, camera_transform) = self.camera.single().ok()?;
+        let cursor_position = self.windows.single().ok()?.cursor_position()?;
+        camera
+            .viewport_to_world_2d(camera_transform, cursor_position)
+            .ok()
+    }
+
+    pub fn grid_position(&self) -> Option<GridPosition> {
+        self.world_position()
+            .map(|point| self.config.world_to_grid(point))
     }
 }
 
 fn setup_hover_borders(
     mut commands: Commands,
+    config: Res<GridConfig>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
@@ -155,7 +188,7 @@ fn setup_hover_borders(
     // Create 4 persistent border entities, initially hidden
     let top = commands
         .spawn((
-            Mesh2d(meshes.add(Rectangle::new(TILE_SIZE as f32, 1.0))),
+            Mesh2d(meshes.add(Rectangle::new(config.tile_size, 1.0))),
             MeshMaterial2d(border_material_handle.clone()),
             Transform::from_translation(Vec3::new(0.0, 0.0, 0.1)),
             Visibility::Hidden,
@@ -165,7 +198,7 @@ fn setup_hover_borders(
 
     let bottom = commands
         .spawn((
-            Mesh2d(meshes.add(Rectangle::new(TILE_SIZE as f32, 1.0))),
+            Mesh2d(meshes.add(Rectangle::new(config.tile_size, 1.0))),
             MeshMaterial2d(border_material_handle.clone()),
             Transform::from_translation(Vec3::new(0.0, 0.0, 0.1)),
             Visibility::Hidden,
@@ -175,7 +208,7 @@ fn setup_hover_borders(
 
     let left = commands
         .spawn((
-            Mesh2d(meshes.add(Rectangle::new(1.0, TILE_SIZE as f32))),
+            Mesh2d(meshes.add(Rectangle::new(1.0, config.tile_size))),
             MeshMaterial2d(border_material_handle.clone()),
             Transform::from_translation(Vec3::new(0.0, 0.0, 0.1)),
             Visibility::Hidden,
@@ -185,7 +218,7 @@ fn setup_hover_borders(
 
     let right = commands
         .spawn((
-            Mesh2d(meshes.add(Rectangle::new(1.0, TILE_SIZE as f32))),
+            Mesh2d(meshes.add(Rectangle::new(1.0, config.tile_size))),
             MeshMaterial2d(border_material_handle),
             Transform::from_translation(Vec3::new(0.0, 0.0, 0.1)),
             Visibility::Hidden,
@@ -202,25 +235,18 @@ fn setup_hover_borders(
 }
 
 fn hover_mouse(
-    windows: Query<&Window>,
-    camera: Query<(&Camera, &GlobalTransform)>,
-    tiles: Query<&GridPosition, With<Hoverable>>,
+    cursor: GridCursor,
+    tile_index: Res<TileIndex>,
     hover_entities: Res<HoverBorderEntities>,
     mut hover_state: ResMut<HoverState>,
+    mut tints: Query<&mut TileTints>,
     mut transforms: Query<&mut Transform>,
     mut visibility: Query<&mut Visibility>,
 ) {
-    let Ok((camera, camera_transform)) = camera.single() else {
-        return;
-    };
-
-    let Ok(window) = windows.single() else {
-        return;
-    };
-    let Some(cursor_position) = window.cursor_position() else {
+    let Some(hovered_tile) = cursor.grid_position() else {
         // Hide borders when cursor is not in window only if we were previously showing something
-        if hover_state.last_hovered.is_some() {
-            hover_state.last_hovered = None;
+        if let Some(last_hovered) = hover_state.last_hovered.take() {
+            tint_tile(&tile_index, &mut tints, last_hovered, None);
             if let Ok(mut vis) = visibility.get_mut(hover_entities.top) {
                 *vis = Visibility::Hidden;
             }
@@ -237,29 +263,23 @@ fn hover_mouse(
         return;
     };
 
-    let world_position = camera
-        .viewport_to_world_2d(camera_transform, cursor_position)
-        .unwrap_or(Vec2::ZERO);
-
-    let grid_x = (world_position.x / TILE_SIZE as f32).round() as i32;
-    let grid_y = (world_position.y / TILE_SIZE as f32).round() as i32;
-    let hovered_tile = GridPosition {
-        x: grid_x,
-        y: grid_y,
-    };
-
     // Early return if we're hovering the same tile as last frame
     if hover_state.last_hovered == Some(hovered_tile) {
         return;
     }
 
+    if let Some(last_hovered) = hover_state.last_hovered {
+        tint_tile(&tile_index, &mut tints, last_hovered, None);
+    }
+
     // Check if we're hovering over a valid tile
-    let is_hovering_valid_tile = tiles.iter().any(|pos| *pos == hovered_tile);
+    let is_hovering_valid_tile = tile_index.tile_loaded(&cursor.config, hovered_tile);
 
     if is_hovering_valid_tile {
         hover_state.last_hovered = Some(hovered_tile);
-        let tile_pos = grid_to_world(hovered_tile);
-        let half_tile = TILE_SIZE as f32 / 2.0;
+        tint_tile(&tile_index, &mut tints, hovered_tile, Some(HOVER_TINT));
+        let tile_pos = cursor.config.grid_to_world(hovered_tile);
+        let half_tile = cursor.config.tile_size / 2.0;
 
         // Update positions and show borders
         if let Ok(mut transform) = transforms.get_mut(hover_entities.top) {
@@ -307,165 +327,125 @@ fn hover_mouse(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn click_place_system(
     buttons: Res<ButtonInput<MouseButton>>,
-    windows: Query<&Window>,
-    camera_q: Query<(&Camera, &GlobalTransform)>,
+    cursor: GridCursor,
     tool: Res<BuildTool>,
+    registry: Res<ItemRegistry>,
     mut commands: Commands,
-    mut tiles: Query<
-        (
-            Entity,
-            &GridPosition,
-            &Material2dHandle,
-            &mut Tile,
-            Option<&TileContent>,
-        ),
-        With<Hoverable>,
-    >,
-    meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
     mut wire_state: ResMut<WireState>,
-    mut connection_points: Query<&mut ConnectionPoint>,
-    items: Query<(Entity, &GridPosition), With<ConnectionPoint>>,
+    connection_points: Query<&mut ConnectionPoint>,
+    wires: Query<(Entity, &Wire)>,
+    ends: WireEnds,
+    wire_index: Res<WireIndex>,
+    tile_index: Res<TileIndex>,
 ) {
-    let Ok((camera, cam_transform)) = camera_q.single() else {
+    let Some(world_pos) = cursor.world_position() else {
         return;
     };
-    let Ok(window) = windows.single() else { return };
-    let Some(cursor_pos) = window.cursor_position() else {
+    let config = &*cursor.config;
+    let tile_pos = config.world_to_grid(world_pos);
+    if !tile_index.tile_loaded(config, tile_pos) {
         return;
-    };
-    let world_pos = match camera.viewport_to_world_2d(cam_transform, cursor_pos) {
-        Ok(pos) => pos,
-        Err(_) => return,
-    };
-
-    let grid_x = (world_pos.x / TILE_SIZE as f32).round() as i32;
-    let grid_y = (world_pos.y / TILE_SIZE as f32).round() as i32;
-    let clicked_pos = GridPosition {
-        x: grid_x,
-        y: grid_y,
-    };
-
-    for (tile_entity, tile_pos, _mat_handle, tile, existing) in tiles.iter_mut() {
-        if *tile_pos != clicked_pos {
-            continue;
-        }
+    }
 
-        if buttons.just_pressed(MouseButton::Right) {
-            match *tool {
-                BuildTool::Wire => {
-                    // Wire placement logic
-                    if let Some(item_entity) = find_item_at_position(*tile_pos, &items) {
-                        // Existing item found - connect to it
-                        handle_wire_placement(
-                            item_entity,
-                            *tile_pos,
-                            &mut wire_state,
-                            &mut connection_points,
-                            &mut commands,
-                        );
-                    } else if wire_state.selected_connection.is_some() && existing.is_none() {
-                        // Empty tile and we have a selected connection - spawn pole and connect
-                        handle_wire_to_empty_tile(
-                            tile_entity,
-                            *tile_pos,
-                            &mut wire_state,
-                            &mut commands,
-                            meshes,
-                            &mut materials,
-                        );
-                    }
+    if buttons.just_pressed(MouseButton::Right) {
+        match &*tool {
+            BuildTool::Wire => {
+                // Wire placement logic
+                if let Some(item_entity) = tile_index.item(tile_pos) {
+                    // Existing item found - connect to it
+                    handle_wire_placement(
+                        item_entity,
+                        tile_pos,
+                        config,
+                        &ends,
+                        &wire_index,
+                        &mut wire_state,
+                        &connection_points,
+                        &mut commands,
+                    );
+                } else if wire_state.selected_connection.is_some() {
+                    // Empty tile and we have a selected connection - spawn pole and connect
+                    handle_wire_to_empty_tile(
+                        tile_pos,
+                        &registry,
+                        &ends,
+                        &connection_points,
+                        &mut wire_state,
+                        &mut commands,
+                    );
                 }
-                _ if existing.is_none() => {
-                    // Regular item placement
-                    match *tool {
-                        BuildTool::Generator => {
-                            let generator = generator::spawn_generator(
-                                &mut commands,
-                                *tile_pos,
-                                meshes,
-                                &mut materials,
-                            );
-
-                            commands.entity(tile_entity).insert(TileContent::Generator);
-                            commands.entity(tile_entity).insert(Tile {
-                                content: Some(generator),
-                            });
-                        }
-                        BuildTool::PowerPole => {
-                            let pole = power_pole::spawn_power_pole(
-                                &mut commands,
-                                *tile_pos,
-                                meshes,
-                                &mut materials,
-                            );
-
-                            commands.entity(tile_entity).insert(TileContent::PowerPole);
-                            commands.entity(tile_entity).insert(Tile {
-                                content: Some(pole),
-                            });
-                        }
-                        BuildTool::Light => {
-                            let light = light::spawn_light(
-                                &mut commands,
-                                *tile_pos,
-                                meshes,
-                                &mut materials,
-                            );
-
-                            commands.entity(tile_entity).insert(TileContent::Light);
-                            commands.entity(tile_entity).insert(Tile {
-                                content: Some(light),
-                            });
-                        }
-                        BuildTool::Wire => {}
-                    }
-                }
-                _ => {}
-            }
-        }
-
-        if buttons.just_pressed(MouseButton::Left) && existing.is_some() {
-            println!("Removing tile at {:?}", tile_pos);
-            if let Some(child_ent) = tile.content {
-                commands.entity(child_ent).despawn();
             }
-            commands
-                .entity(tile_entity)
-                .remove::<TileContent>()
-                .insert(Tile { content: None });
+            // The other tools handle their own clicks
+            BuildTool::Place(_) | BuildTool::Select | BuildTool::Blueprint | BuildTool::Fuel => {}
         }
-
-        break;
     }
-}
 
-fn find_item_at_position(
-    pos: GridPosition,
-    items: &Query<(Entity, &GridPosition), With<ConnectionPoint>>,
-) -> Option<Entity> {
-    for (entity, item_pos) in items.iter() {
-        if *item_pos == pos {
-            return Some(entity);
+    if buttons.just_pressed(MouseButton::Left) {
+        // The wire tool removes the wire under the cursor before touching items
+        let picked_wire = if *tool == BuildTool::Wire {
+            let segments = wires.iter().filter_map(|(wire_entity, wire)| {
+                Some((
+                    wire_entity,
+                    wire_end(&ends, config, wire.from, wire.from_port)?,
+                    wire_end(&ends, config, wire.to, wire.to_port)?,
+                ))
+            });
+            wire_near(world_pos, segments, WIRE_PICK_DISTANCE)
+        } else {
+            None
+        };
+
+        if let Some(wire_entity) = picked_wire {
+            println!("Removing wire {:?}", wire_entity);
+            commands
+                .queue(move |world: &mut World| history::remove_wire_recorded(world, wire_entity));
+        } else if tile_index.item(tile_pos).is_some() {
+            println!("Removing tile at {:?}", tile_pos);
+            // Removal takes the item's wires with it and is recorded for undo
+            commands.queue(move |world: &mut World| history::remove_item_recorded(world, tile_pos));
         }
     }
-    None
 }
 
+#[allow(clippy::too_many_arguments)]
 fn handle_wire_placement(
     item_entity: Entity,
     pos: GridPosition,
+    config: &GridConfig,
+    ends: &WireEnds,
+    wire_index: &WireIndex,
     wire_state: &mut ResMut<WireState>,
-    connection_points: &mut Query<&mut ConnectionPoint>,
+    connection_points: &Query<&mut ConnectionPoint>,
     commands: &mut Commands,
 ) {
     if let Some(selected) = wire_state.selected_connection {
-        // Second click - try to create wire
-        if selected != item_entity {
-            if can_connect(selected, item_entity, connection_points) {
-                create_wire(selected, item_entity, connection_points, commands);
+        // Second click - clicking an already wired pair again removes the wire,
+        // otherwise try to create one
+        if let Some(wire_entity) = wire_index.get(selected, item_entity) {
+            println!("Removing wire between {:?} and {:?}", selected, item_entity);
+            commands
+                .queue(move |world: &mut World| history::remove_wire_recorded(world, wire_entity));
+        } else {
+            match check_connection(
+                selected,
+                item_entity,
+                connection_points,
+                wire_index,
+                ends,
+                config,
+            ) {
+                Ok(ports) => {
+                    let kind = wire_state.kind;
+                    commands.queue(move |world: &mut World| {
+                        history::connect_recorded(world, selected, item_entity, kind, ports)
+                    });
+                }
+                // Clicking the selected item again just deselects it
+                Err(ConnectionError::SameItem) => {}
+                Err(reason) => println!("Cannot connect: {}", reason),
             }
         }
         // Clear selection and preview
@@ -475,87 +455,69 @@ fn handle_wire_placement(
         wire_state.preview_entity = None;
     } else {
         // First click - select connection point
-        if let Ok(connection_point) = connection_points.get(item_entity) {
-            if connection_point.can_connect() {
-                wire_state.selected_connection = Some(item_entity);
-                wire_state.selected_position = Some(grid_to_world(pos));
-                println!("Selected connection point at {:?}", pos);
-            }
+        if let Ok(connection_point) = connection_points.get(item_entity)
+            && connection_point.can_connect()
+        {
+            wire_state.selected_connection = Some(item_entity);
+            wire_state.selected_position = Some(config.grid_to_world(pos));
+            println!("Selected connection point at {:?}", pos);
         }
     }
 }
 
-fn can_connect(
-    from: Entity,
-    to: Entity,
-    connection_points: &mut Query<&mut ConnectionPoint>,
-) -> bool {
-    if let (Ok(from_conn), Ok(to_conn)) = (connection_points.get(from), connection_points.get(to)) {
-        from_conn.can_connect() && to_conn.can_connect()
-    } else {
-        false
-    }
-}
-
-fn create_wire(
-    from: Entity,
-    to: Entity,
-    connection_points: &mut Query<&mut ConnectionPoint>,
-    commands: &mut Commands,
-) {
-    // Validate that both entities exist and have connection points
-    let from_valid = connection_points.get(from).is_ok();
-    let to_valid = connection_points.get(to).is_ok();
-
-    if !from_valid || !to_valid {
-        println!(
-            "Cannot create wire: invalid entities (from: {}, to: {})",
-            from_valid, to_valid
-        );
-        return;
-    }
-
-    let wire_entity = commands
-        .spawn((
-            Wire { from, to },
-            Name::new("Wire"),
-            // Visual representation will be added by wire_visual_system
-        ))
-        .id();
-
-    // Add wire to connection points
-    if let Ok(mut from_conn) = connection_points.get_mut(from) {
-        from_conn.add_connection(wire_entity);
-    }
-    if let Ok(mut to_conn) = connection_points.get_mut(to) {
-        to_conn.add_connection(wire_entity);
-    }
-
-    println!("Created wire between {:?} and {:?}", from, to);
-}
-
 fn handle_wire_to_empty_tile(
-    tile_entity: Entity,
     pos: GridPosition,
+    registry: &ItemRegistry,
+    ends: &WireEnds,
+    connection_points: &Query<&mut ConnectionPoint>,
     wire_state: &mut ResMut<WireState>,
     commands: &mut Commands,
-    meshes: ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<ColorMaterial>>,
 ) {
     if let Some(selected_entity) = wire_state.selected_connection {
-        // Spawn a power pole at the empty tile
-        let pole_entity = power_pole::spawn_power_pole(commands, pos, meshes, materials);
-
-        // Update the tile to contain the pole
-        commands.entity(tile_entity).insert(TileContent::PowerPole);
-        commands.entity(tile_entity).insert(Tile {
-            content: Some(pole_entity),
-        });
+        let pole_id = power_pole::power_pole_id();
+        let Some(definition) = registry.get(&pole_id) else {
+            println!(
+                "Cannot spawn power pole: no item definition for {}",
+                pole_id
+            );
+            return;
+        };
+
+        // Don't drop a pole the wire couldn't reach; keep the selection so the
+        // user can click somewhere closer
+        if let (Ok((selected_pos, _, _)), Ok(selected_point)) = (
+            ends.get(selected_entity),
+            connection_points.get(selected_entity),
+        ) {
+            let reach = selected_point.reach.min(definition.reach);
+            let distance = tile_distance(*selected_pos, pos);
+            if distance > reach {
+                println!(
+                    "Cannot place pole: {}",
+                    ConnectionError::OutOfReach { distance, reach }
+                );
+                return;
+            }
+        }
 
-        // Schedule wire creation for next frame when pole components are ready
-        commands.spawn(PendingWireConnection {
-            from: selected_entity,
-            to: pole_entity,
+        // Placed like any other item so bounds, footprint and the tile index
+        // all apply, then wired in the same command
+        let kind = wire_state.kind;
+        commands.queue(move |world: &mut World| {
+            let Some(pole) = edit::place_item(world, &pole_id, pos, Facing::default()) else {
+                println!("Cannot place pole at {:?}", pos);
+                return;
+            };
+            let wire = edit::connect(world, selected_entity, pole, kind)
+                .and_then(|wire| WireSnapshot::capture(world, world.get::<Wire>(wire)?));
+
+            // Undoing removes the pole and its wire together
+            let placed = Edit::PlaceItem(ItemSnapshot::new(pole_id, pos, Facing::default()));
+            world.resource_mut::<EditHistory>().record(match wire {
+                Some(wire) => Edit::Compound(vec![placed, Edit::CreateWire(wire)]),
+                None => placed,
+            });
+            println!("Spawned power pole and connected wire at {:?}", pos);
         });
 
         // Clear selection and preview
@@ -563,56 +525,5 @@ fn handle_wire_to_empty_tile(
         wire_state.selected_position = None;
         // Just clear the reference, preview system will handle cleanup
         wire_state.preview_entity = None;
-
-        println!(
-            "Spawned power pole and scheduled wire connection at {:?}",
-            pos
-        );
-    }
-}
-
-fn process_pending_wire_connections(
-    mut commands: Commands,
-    mut connection_points: Query<&mut ConnectionPoint>,
-    pending_connections: Query<(Entity, &PendingWireConnection)>,
-    existing_entities: Query<Entity>,
-) {
-    for (pending_entity, pending) in pending_connections.iter() {
-        // Check if both entities still exist
-        let from_exists = existing_entities.get(pending.from).is_ok();
-        let to_exists = existing_entities.get(pending.to).is_ok();
-
-        if !from_exists || !to_exists {
-            // One or both entities no longer exist, cancel the pending connection
-            println!(
-                "Cancelling pending wire connection - entity no longer exists (from: {}, to: {})",
-                from_exists, to_exists
-            );
-            commands.entity(pending_entity).despawn();
-            continue;
-        }
-
-        // Check if both entities now have ConnectionPoint components
-        if connection_points.get(pending.from).is_ok() && connection_points.get(pending.to).is_ok()
-        {
-            // Create the wire connection
-            if can_connect(pending.from, pending.to, &mut connection_points) {
-                create_wire(
-                    pending.from,
-                    pending.to,
-                    &mut connection_points,
-                    &mut commands,
-                );
-                println!(
-                    "Successfully created deferred wire connection between {:?} and {:?}",
-                    pending.from, pending.to
-                );
-            } else {
-                println!("Failed to connect - connection points at capacity");
-            }
-
-            // Remove the pending connection
-            commands.entity(pending_entity).despawn();
-        }
     }
 }
@@ -0,0 +1,410 @@
+use crate::items::definition::ItemId;
+use crate::items::registry::ItemRegistry;
+use crate::save::{ItemSnapshot, WireSnapshot};
+use crate::wire_system::auto_wire::{AutoWire, auto_wire};
+use crate::wire_system::port::Facing;
+use crate::wire_system::{Wire, WireKind};
+use crate::world::edit;
+use crate::world::grid::{Footprint, GridPosition};
+use bevy::prelude::*;
+
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EditHistory>()
+            .add_systems(Update, undo_redo_input);
+    }
+}
+
+/// A reversible change to the world, expressed in grid positions so it can be
+/// replayed after the entities involved have been despawned and respawned
+#[derive(Clone)]
+pub enum Edit {
+    PlaceItem(ItemSnapshot),
+    /// The item's state and every wire it had when it was removed
+    RemoveItem {
+        item: ItemSnapshot,
+        wires: Vec<WireSnapshot>,
+    },
+    CreateWire(WireSnapshot),
+    RemoveWire(WireSnapshot),
+    /// Several edits undone and redone as one, e.g. an auto-spawned pole and its wire
+    Compound(Vec<Edit>),
+}
+
+impl Edit {
+    /// Performs the edit (again)
+    pub fn apply(&self, world: &mut World) {
+        match self {
+            Edit::PlaceItem(item) => {
+                item.restore(world);
+            }
+            Edit::RemoveItem { item, .. } => {
+                edit::remove_item(world, item.pos);
+            }
+            Edit::CreateWire(wire) => {
+                wire.restore(world);
+            }
+            Edit::RemoveWire(wire) => {
+                remove_wire_at(world, *wire);
+            }
+            Edit::Compound(edits) => {
+                for edit in edits {
+                    edit.apply(world);
+                }
+            }
+        }
+    }
+
+    /// Reverses the edit
+    pub fn revert(&self, world: &mut World) {
+        match self {
+            Edit::PlaceItem(item) => {
+                edit::remove_item(world, item.pos);
+            }
+            Edit::RemoveItem { item, wires } => {
+                item.restore(world);
+                for wire in wires {
+                    wire.restore(world);
+                }
+            }
+            Edit::CreateWire(wire) => {
+                remove_wire_at(world, *wire);
+            }
+            Edit::RemoveWire(wire) => {
+                wire.restore(world);
+            }
+            Edit::Compound(edits) => {
+                for edit in edits.iter().rev() {
+                    edit.revert(world);
+                }
+            }
+        }
+    }
+}
+
+fn remove_wire_at(world: &mut World, wire: WireSnapshot) -> bool {
+    let (Some(from), Some(to)) = (
+        edit::item_at(world, wire.from),
+        edit::item_at(world, wire.to),
+    ) else {
+        return false;
+    };
+    edit::wire_between(world, from, to)
+        .is_some_and(|wire_entity| edit::remove_wire(world, wire_entity))
+}
+
+#[derive(Resource, Default)]
+pub struct EditHistory {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+}
+
+impl EditHistory {
+    /// Pushes a freshly performed edit; anything that could be redone is dropped
+    pub fn record(&mut self, edit: Edit) {
+        self.undo.push(edit);
+        self.redo.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+/// Places `id` at each of `positions`, skipping blocked ones, and records
+/// everything placed as a single edit. Poles are wired up as they go down
+/// while auto-wiring is on.
+pub fn place_items_recorded(
+    world: &mut World,
+    id: &ItemId,
+    positions: &[GridPosition],
+    facing: Facing,
+) {
+    let wire_up = world.resource::<AutoWire>().enabled
+        && world
+            .resource::<ItemRegistry>()
+            .get(id)
+            .is_some_and(|definition| definition.pole);
+
+    let mut placed: Vec<Edit> = Vec::new();
+    for pos in positions {
+        let Some(item) = edit::place_item(world, id, *pos, facing) else {
+            continue;
+        };
+        placed.push(Edit::PlaceItem(ItemSnapshot::new(id.clone(), *pos, facing)));
+        if wire_up {
+            for wire_entity in auto_wire(world, item) {
+                if let Some(wire) = world
+                    .get(wire_entity)
+                    .and_then(|wire| WireSnapshot::capture(world, wire))
+                {
+                    placed.push(Edit::CreateWire(wire));
+                }
+            }
+        }
+    }
+
+    let edit = match placed.len() {
+        0 => return,
+        1 => placed.remove(0),
+        _ => Edit::Compound(placed),
+    };
+    world.resource_mut::<EditHistory>().record(edit);
+}
+
+/// Removes the item at `pos` and records it so it can be restored with its wires
+pub fn remove_item_recorded(world: &mut World, pos: GridPosition) {
+    let Some(edit) = edit::item_at(world, pos).and_then(|item| remove_item_edit(world, item))
+    else {
+        return;
+    };
+    world.resource_mut::<EditHistory>().record(edit);
+}
+
+/// Removes all of `items` with their wires, recorded as one edit
+pub fn remove_items_recorded(world: &mut World, items: &[Entity]) {
+    let removed: Vec<Edit> = items
+        .iter()
+        .filter_map(|item| remove_item_edit(world, *item))
+        .collect();
+    if !removed.is_empty() {
+        world
+            .resource_mut::<EditHistory>()
+            .record(Edit::Compound(removed));
+    }
+}
+
+/// Moves `items` by `offset`, or duplicates them there with `copy`, together
+/// with the wires between them, recorded as one edit. Wires to items left
+/// behind are dropped. Returns the items at the new place.
+pub fn shift_items_recorded(
+    world: &mut World,
+    items: &[Entity],
+    offset: IVec2,
+    copy: bool,
+) -> Vec<Entity> {
+    let ignore: &[Entity] = if copy { &[] } else { items };
+    let blocked = items.iter().any(|item| {
+        let (Some(pos), Some(footprint)) = (
+            world.get::<GridPosition>(*item),
+            world.get::<Footprint>(*item),
+        ) else {
+            return true;
+        };
+        edit::footprint_blocked(world, pos.offset(offset), *footprint, ignore)
+    });
+    if blocked {
+        println!(
+            "Cannot {} selection: target is blocked",
+            if copy { "copy" } else { "move" }
+        );
+        return Vec::new();
+    }
+
+    let snapshots: Vec<ItemSnapshot> = items
+        .iter()
+        .filter_map(|item| ItemSnapshot::capture(world, *item))
+        .collect();
+    let wires: Vec<WireSnapshot> = world
+        .query::<&Wire>()
+        .iter(world)
+        .filter(|wire| items.contains(&wire.from) && items.contains(&wire.to))
+        .filter_map(|wire| WireSnapshot::capture(world, wire))
+        .collect();
+
+    let mut edits: Vec<Edit> = Vec::new();
+    if !copy {
+        edits.extend(
+            items
+                .iter()
+                .filter_map(|item| remove_item_edit(world, *item)),
+        );
+    }
+
+    let shifted = place_snapshots(world, &snapshots, &wires, offset, &mut edits);
+
+    if !edits.is_empty() {
+        world
+            .resource_mut::<EditHistory>()
+            .record(Edit::Compound(edits));
+    }
+    shifted
+}
+
+/// Places a group of items and the wires between them at `offset`, recorded
+/// as one edit. Nothing is placed if any of the items would be blocked.
+pub fn place_group_recorded(
+    world: &mut World,
+    items: &[ItemSnapshot],
+    wires: &[WireSnapshot],
+    offset: IVec2,
+) -> Vec<Entity> {
+    let blocked = items
+        .iter()
+        .filter(|item| {
+            let footprint = world
+                .resource::<ItemRegistry>()
+                .get(&item.id)
+                .map_or_else(Footprint::default, |definition| {
+                    item.facing.rotate_footprint(definition.footprint)
+                });
+            edit::footprint_blocked(world, item.pos.offset(offset), footprint, &[])
+        })
+        .count();
+    if blocked > 0 {
+        println!(
+            "Cannot place group: {} of {} items are blocked",
+            blocked,
+            items.len()
+        );
+        return Vec::new();
+    }
+
+    let mut edits = Vec::new();
+    let placed = place_snapshots(world, items, wires, offset, &mut edits);
+    if !edits.is_empty() {
+        world
+            .resource_mut::<EditHistory>()
+            .record(Edit::Compound(edits));
+    }
+    placed
+}
+
+/// Restores `items` and then `wires` moved by `offset`, pushing an edit for
+/// each one that succeeds
+fn place_snapshots(
+    world: &mut World,
+    items: &[ItemSnapshot],
+    wires: &[WireSnapshot],
+    offset: IVec2,
+    edits: &mut Vec<Edit>,
+) -> Vec<Entity> {
+    let mut placed = Vec::new();
+    for item in items {
+        let moved = item.offset(offset);
+        if let Some(entity) = moved.restore(world) {
+            placed.push(entity);
+            edits.push(Edit::PlaceItem(moved));
+        }
+    }
+    for wire in wires {
+        let moved = wire.offset(offset);
+        if moved.restore(world).is_some() {
+            edits.push(Edit::CreateWire(moved));
+        }
+    }
+    placed
+}
+
+/// Removes `item` with its wires, returning the edit that restores them
+fn remove_item_edit(world: &mut World, item_entity: Entity) -> Option<Edit> {
+    let item = ItemSnapshot::capture(world, item_entity)?;
+    let wires: Vec<WireSnapshot> = edit::wires_of(world, item_entity)
+        .into_iter()
+        .filter_map(|wire_entity| {
+            world
+                .get(wire_entity)
+                .and_then(|wire| WireSnapshot::capture(world, wire))
+        })
+        .collect();
+
+    edit::remove_item(world, item.pos).then_some(Edit::RemoveItem { item, wires })
+}
+
+/// Wires `from` to `to` on `ports` and records the wire by its items' anchors
+pub fn connect_recorded(
+    world: &mut World,
+    from: Entity,
+    to: Entity,
+    kind: WireKind,
+    ports: (Option<u8>, Option<u8>),
+) {
+    let Some(wire) = edit::connect_at(world, from, to, kind, Some(ports))
+        .and_then(|wire_entity| WireSnapshot::capture(world, world.get::<Wire>(wire_entity)?))
+    else {
+        return;
+    };
+
+    println!("Created wire between {:?} and {:?}", from, to);
+    world
+        .resource_mut::<EditHistory>()
+        .record(Edit::CreateWire(wire));
+}
+
+/// Removes a single wire and records it so it can be reconnected
+pub fn remove_wire_recorded(world: &mut World, wire_entity: Entity) {
+    let Some(wire) = world
+        .get(wire_entity)
+        .and_then(|wire| WireSnapshot::capture(world, wire))
+    else {
+        return;
+    };
+
+    if edit::remove_wire(world, wire_entity) {
+        world
+            .resource_mut::<EditHistory>()
+            .record(Edit::RemoveWire(wire));
+    }
+}
+
+fn undo_redo_input(world: &mut World) {
+    let keys = world.resource::<ButtonInput<KeyCode>>();
+    if !keys.just_pressed(KeyCode::KeyZ)
+        || !keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+    {
+        return;
+    }
+    let redo = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    world.resource_scope(|world, mut history: Mut<EditHistory>| {
+        if redo {
+            if let Some(edit) = history.redo.pop() {
+                edit.apply(world);
+                history.undo.push(edit);
+            }
+        } else if let Some(edit) = history.undo.pop() {
+            edit.revert(world);
+            history.redo.push(edit);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::save::{CameraState, SAVE_VERSION, SaveFile};
+    use crate::wire_system::WireIndex;
+
+    #[test]
+    fn loading_a_save_clears_the_history() {
+        let mut world = World::new();
+        world.init_resource::<WireIndex>();
+        let wire = WireSnapshot {
+            from: GridPosition { x: 0, y: 0 },
+            to: GridPosition { x: 3, y: 0 },
+            kind: WireKind::Copper,
+            from_port: None,
+            to_port: None,
+        };
+        let mut history = EditHistory::default();
+        history.record(Edit::CreateWire(wire));
+        history.redo.push(Edit::RemoveWire(wire));
+        world.insert_resource(history);
+
+        let save = SaveFile {
+            version: SAVE_VERSION,
+            camera: CameraState::default(),
+            clock: None,
+            items: Vec::new(),
+            wires: Vec::new(),
+        };
+        save.restore(&mut world);
+
+        let history = world.resource::<EditHistory>();
+        assert!(history.undo.is_empty());
+        assert!(history.redo.is_empty());
+    }
+}
@@ -0,0 +1,130 @@
+use crate::items::registry::Item;
+use crate::world::chunk::{Chunk, ChunkPosition};
+use crate::world::grid::{Footprint, GridConfig, GridPosition};
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+pub struct TileIndexPlugin;
+
+impl Plugin for TileIndexPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TileIndex>()
+            .add_observer(on_item_added)
+            .add_observer(on_item_removed)
+            .add_observer(on_chunk_added)
+            .add_observer(on_chunk_removed);
+    }
+}
+
+/// Grid position lookups for items and loaded chunks, kept in sync by
+/// observers so nothing has to scan every item to find one. Tiles aren't
+/// entities; a tile exists wherever its chunk is loaded inside the grid.
+#[derive(Resource, Default)]
+pub struct TileIndex {
+    items: HashMap<GridPosition, Entity>,
+    chunks: HashMap<ChunkPosition, Entity>,
+    // Tiles covered by items in each chunk; chunks holding items must stay loaded
+    occupied_per_chunk: HashMap<ChunkPosition, usize>,
+}
+
+impl TileIndex {
+    /// Whether the tile at `pos` is inside the grid and its chunk is loaded
+    pub fn tile_loaded(&self, config: &GridConfig, pos: GridPosition) -> bool {
+        config.contains(pos) && self.chunks.contains_key(&ChunkPosition::of(pos))
+    }
+
+    pub fn item(&self, pos: GridPosition) -> Option<Entity> {
+        self.items.get(&pos).copied()
+    }
+
+    pub fn chunk(&self, chunk: ChunkPosition) -> Option<Entity> {
+        self.chunks.get(&chunk).copied()
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item = (ChunkPosition, Entity)> + '_ {
+        self.chunks.iter().map(|(chunk, entity)| (*chunk, *entity))
+    }
+
+    pub fn occupied_in_chunk(&self, chunk: ChunkPosition) -> usize {
+        self.occupied_per_chunk.get(&chunk).copied().unwrap_or(0)
+    }
+
+    /// Whether `footprint` at `anchor` would leave the grid or overlap an item
+    /// other than those in `ignore`
+    pub fn footprint_blocked(
+        &self,
+        config: &GridConfig,
+        anchor: GridPosition,
+        footprint: Footprint,
+        ignore: &[Entity],
+    ) -> bool {
+        footprint.tiles(anchor).any(|pos| {
+            !config.contains(pos) || self.item(pos).is_some_and(|item| !ignore.contains(&item))
+        })
+    }
+}
+
+fn on_item_added(
+    trigger: Trigger<OnAdd, Item>,
+    items: Query<(&GridPosition, Option<&Footprint>)>,
+    mut index: ResMut<TileIndex>,
+) {
+    let Ok((anchor, footprint)) = items.get(trigger.target()) else {
+        return;
+    };
+
+    for pos in footprint.copied().unwrap_or_default().tiles(*anchor) {
+        index.items.insert(pos, trigger.target());
+        *index
+            .occupied_per_chunk
+            .entry(ChunkPosition::of(pos))
+            .or_default() += 1;
+    }
+}
+
+fn on_item_removed(
+    trigger: Trigger<OnRemove, Item>,
+    items: Query<(&GridPosition, Option<&Footprint>)>,
+    mut index: ResMut<TileIndex>,
+) {
+    let Ok((anchor, footprint)) = items.get(trigger.target()) else {
+        return;
+    };
+
+    for pos in footprint.copied().unwrap_or_default().tiles(*anchor) {
+        if index.items.get(&pos) != Some(&trigger.target()) {
+            continue;
+        }
+
+        index.items.remove(&pos);
+        let chunk = ChunkPosition::of(pos);
+        if let Some(count) = index.occupied_per_chunk.get_mut(&chunk) {
+            *count -= 1;
+            if *count == 0 {
+                index.occupied_per_chunk.remove(&chunk);
+            }
+        }
+    }
+}
+
+fn on_chunk_added(
+    trigger: Trigger<OnAdd, Chunk>,
+    chunks: Query<&Chunk>,
+    mut index: ResMut<TileIndex>,
+) {
+    if let Ok(chunk) = chunks.get(trigger.target()) {
+        index.chunks.insert(chunk.0, trigger.target());
+    }
+}
+
+fn on_chunk_removed(
+    trigger: Trigger<OnRemove, Chunk>,
+    chunks: Query<&Chunk>,
+    mut index: ResMut<TileIndex>,
+) {
+    if let Ok(chunk) = chunks.get(trigger.target())
+        && index.chunks.get(&chunk.0) == Some(&trigger.target())
+    {
+        index.chunks.remove(&chunk.0);
+    }
+}